@@ -1,64 +1,331 @@
-use alloc::raw_vec::RawVec;
-use std::convert::From;
-use std::ptr;
+use std::any::Any;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Range;
+use std::slice;
+use std::str;
+use std::sync::Arc;
 
-use dtypes::{DataType, PrimitiveType, ListType, List};
+use bitmap::{self, BitMap};
+use buffer::MutableBuffer;
+use dtypes::*;
+use errors::{ArrowError, StatusCode, Result};
+use half::f16;
+use mempool::{MemoryPoolRef, default_pool};
+use temporal::{Date, Time, DateTime};
+use types;
 
 
-pub type Buffer<T> = RawVec<T>;
-pub type BitMap = Buffer<bool>;
+// type erased array, so columns of different types can live together
+pub trait Array: Send + Sync {
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn data_type(&self) -> types::DataType;
+
+    fn null_count(&self) -> usize;
+
+    fn is_null(&self, i: usize) -> bool;
+
+    fn is_valid(&self, i: usize) -> bool {
+        !self.is_null(i)
+    }
+
+}
+
+
+pub type ArrayRef = Arc<dyn Array>;
 
 
 pub struct PrimitiveData<T: PrimitiveType>{
-    values: Buffer<T::Item>
+    values: MutableBuffer,
+    dtype: PhantomData<T>
+}
+
+
+pub struct BooleanData {
+    values: BitMap
+}
+
+
+pub struct BinaryData<O: OffsetSize> {
+    // len + 1 offsets once the first value is pushed, empty before
+    offsets: MutableBuffer,
+    values: MutableBuffer,
+    offset: PhantomData<O>
+}
+
+
+pub struct FixedSizeBinaryData {
+    // len * byte_width bytes
+    values: MutableBuffer
+}
+
+
+// unscaled i128 values, kept apart from PrimitiveData so every push is validated
+pub struct DecimalData {
+    values: MutableBuffer
+}
+
+
+pub struct FixedSizeListData<T: DataType> {
+    // list_size values per slot, no offsets needed
+    values: TypedArray<T>
+}
+
+
+pub struct StructData {
+    // one per field, all of the same length
+    children: Vec<ArrayRef>
+}
+
+
+pub struct UnionData {
+    type_ids: MutableBuffer,
+    // i32 index into the child of each slot, dense mode only
+    offsets: MutableBuffer,
+    children: Vec<ArrayRef>
+}
+
+
+pub struct DictionaryData<K: IndexType, V: DataType> {
+    // nulls are tracked by the dictionary array, null slots hold zero keys
+    keys: TypedArray<K>,
+    values: Arc<TypedArray<V>>
+}
+
+
+pub struct MapData {
+    offsets: MutableBuffer,
+    entries: TypedArray<Struct>
 }
 
 
-pub struct ListData<T: DataType> {
-    offsets: Buffer<u32>,
-    values: Array<T>
+pub struct ListData<T: DataType, O: OffsetSize> {
+    // same layout as the offsets of BinaryData
+    offsets: MutableBuffer,
+    values: TypedArray<T>,
+    offset: PhantomData<O>
 }
 
 
 impl<T: PrimitiveType> PrimitiveData<T> {
 
+    pub fn new(pool: &MemoryPoolRef) -> Self {
+        PrimitiveData {
+            values: MutableBuffer::with_pool(pool.clone()),
+            dtype: PhantomData
+        }
+    }
+
+}
+
+
+impl BooleanData {
+
+    pub fn new(pool: &MemoryPoolRef) -> Self {
+        BooleanData { values: BitMap::with_pool(pool.clone()) }
+    }
+
+}
+
+
+impl<O: OffsetSize> BinaryData<O> {
+
+    pub fn new(pool: &MemoryPoolRef) -> Self {
+        BinaryData {
+            offsets: MutableBuffer::with_pool(pool.clone()),
+            values: MutableBuffer::with_pool(pool.clone()),
+            offset: PhantomData
+        }
+    }
+
+}
+
+
+impl FixedSizeBinaryData {
+
+    pub fn new(pool: &MemoryPoolRef) -> Self {
+        FixedSizeBinaryData { values: MutableBuffer::with_pool(pool.clone()) }
+    }
+
+}
+
+
+impl DecimalData {
+
+    pub fn new(pool: &MemoryPoolRef) -> Self {
+        DecimalData { values: MutableBuffer::with_pool(pool.clone()) }
+    }
+
+}
+
+
+impl<T: DataType> FixedSizeListData<T> {
+
+    pub fn new(dtype: T, pool: &MemoryPoolRef) -> Self {
+        FixedSizeListData { values: TypedArray::with_pool(dtype, pool.clone()) }
+    }
+
+}
+
+
+impl StructData {
+
     pub fn new() -> Self {
-        PrimitiveData { values: Buffer::new() }
+        StructData { children: Vec::new() }
+    }
+
+}
+
+
+impl Default for StructData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+impl UnionData {
+
+    pub fn new(pool: &MemoryPoolRef) -> Self {
+        UnionData {
+            type_ids: MutableBuffer::with_pool(pool.clone()),
+            offsets: MutableBuffer::with_pool(pool.clone()),
+            children: Vec::new()
+        }
     }
 
 }
 
 
-impl<T: DataType> ListData<T> {
+impl<K: IndexType, V: DataType> DictionaryData<K, V> {
 
-    pub fn new(dtype: T) -> Self {
+    pub fn new(index: K, values: V, pool: &MemoryPoolRef) -> Self {
+        DictionaryData {
+            keys: TypedArray::with_pool(index, pool.clone()),
+            values: Arc::new(TypedArray::with_pool(values, pool.clone()))
+        }
+    }
+
+}
+
+
+impl MapData {
+
+    pub fn new(entries: Struct, pool: &MemoryPoolRef) -> Self {
+        MapData {
+            offsets: MutableBuffer::with_pool(pool.clone()),
+            entries: TypedArray::with_pool(entries, pool.clone())
+        }
+    }
+
+}
+
+
+impl<T: DataType, O: OffsetSize> ListData<T, O> {
+
+    pub fn new(dtype: T, pool: &MemoryPoolRef) -> Self {
         ListData {
-            offsets: Buffer::new(),
-            values: Array::new(dtype)
+            offsets: MutableBuffer::with_pool(pool.clone()),
+            values: TypedArray::with_pool(dtype, pool.clone()),
+            offset: PhantomData
         }
     }
+
+    pub fn offsets(&self) -> &MutableBuffer {
+        &self.offsets
+    }
+
+    pub fn values(&self) -> &TypedArray<T> {
+        &self.values
+    }
+}
+
+
+
+// variable length layouts, slot i spans offsets[i]..offsets[i + 1]
+pub trait OffsetsData {
+    type Offset: OffsetSize;
+
+    fn offsets(&self) -> &MutableBuffer;
+    fn offsets_mut(&mut self) -> &mut MutableBuffer;
+}
+
+
+impl<O: OffsetSize> OffsetsData for BinaryData<O> {
+    type Offset = O;
+
+    fn offsets(&self) -> &MutableBuffer {
+        &self.offsets
+    }
+
+    fn offsets_mut(&mut self) -> &mut MutableBuffer {
+        &mut self.offsets
+    }
 }
 
 
+impl<T: DataType, O: OffsetSize> OffsetsData for ListData<T, O> {
+    type Offset = O;
+
+    fn offsets(&self) -> &MutableBuffer {
+        &self.offsets
+    }
+
+    fn offsets_mut(&mut self) -> &mut MutableBuffer {
+        &mut self.offsets
+    }
+}
+
+
+impl OffsetsData for MapData {
+    type Offset = i32;
+
+    fn offsets(&self) -> &MutableBuffer {
+        &self.offsets
+    }
+
+    fn offsets_mut(&mut self) -> &mut MutableBuffer {
+        &mut self.offsets
+    }
+}
 
-struct Array<T: DataType> {
+
+pub struct TypedArray<T: DataType> {
     // atomic stuff etc.
     len: usize,
     dtype: T,
-    nulls: BitMap,
+    // allocated when the first null is pushed
+    nulls: Option<BitMap>,
+    null_count: usize,
+    pool: MemoryPoolRef,
     data: T::Data
 }
 
 
-impl<T> Array<T> where T: DataType + Copy {
+impl<T> TypedArray<T> where T: DataType {
+
+    pub fn new(dtype: T) -> Self {
+        Self::with_pool(dtype, default_pool())
+    }
 
-    fn new(dtype: T) -> Self {
-        let data = dtype.empty();
-        Array {
+    pub fn with_pool(dtype: T, pool: MemoryPoolRef) -> Self {
+        let data = dtype.empty(&pool);
+        TypedArray {
             len: 0,
-            dtype: dtype,
-            nulls: BitMap::new(),
-            data: data
+            dtype,
+            nulls: None,
+            null_count: 0,
+            pool,
+            data
         }
     }
 
@@ -66,66 +333,2258 @@ impl<T> Array<T> where T: DataType + Copy {
         self.len
     }
 
-    pub fn dtype(&self) -> T { 
-        self.dtype
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn dtype(&self) -> T {
+        self.dtype.clone()
+    }
+
+    pub fn pool(&self) -> &MemoryPoolRef {
+        &self.pool
+    }
+
+    // None if the array has never contained a null
+    pub fn nulls(&self) -> Option<&BitMap> {
+        self.nulls.as_ref()
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.null_count
+    }
+
+    pub fn is_valid(&self, i: usize) -> bool {
+        assert!(i < self.len, "index {} out of bounds of array with length {}", i, self.len);
+        self.nulls.as_ref().is_none_or(|nulls| nulls.get(i))
+    }
+
+    pub fn is_null(&self, i: usize) -> bool {
+        !self.is_valid(i)
+    }
+
+    // takes over a validity bitmap built by the caller
+    fn set_nulls(&mut self, nulls: Option<BitMap>) -> Result<()> {
+        if let Some(ref nulls) = nulls {
+            if nulls.len() != self.len {
+                return Err(ArrowError::new(StatusCode::Invalid, format!(
+                    "validity of length {} for array of length {}", nulls.len(), self.len)));
+            }
+        }
+        self.null_count = nulls.as_ref().map_or(0, |nulls| nulls.len() - nulls.count_set_bits());
+        self.nulls = nulls;
+        Ok(())
+    }
+
+    // records the validity of n slots appended by the caller
+    fn push_validity(&mut self, n: usize, valid: bool) -> Result<()> {
+        if valid {
+            if let Some(ref mut nulls) = self.nulls {
+                nulls.append_n(n, true)?;
+            }
+        } else {
+            if self.nulls.is_none() {
+                let mut nulls = BitMap::with_pool(self.pool.clone());
+                nulls.append_n(self.len, true)?;
+                self.nulls = Some(nulls);
+            }
+            if let Some(ref mut nulls) = self.nulls {
+                nulls.append_n(n, false)?;
+            }
+            self.null_count += n;
+        }
+        Ok(())
+    }
+
+}
+
+
+impl<T> TypedArray<T> where T: PrimitiveType<Data=PrimitiveData<T>> {
+
+    // reserves first, so a failed allocation leaves the array untouched
+    fn push_value(&mut self, val: T::Item, valid: bool) -> Result<()> {
+        self.data.values.reserve(mem::size_of::<T::Item>())?;
+        self.push_validity(1, valid)?;
+        self.data.values.push(val)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn push(&mut self, val: T::Item) -> Result<()> {
+        self.push_value(val, true)
+    }
+
+    // null slots hold zeroed values
+    pub fn push_null(&mut self) -> Result<()> {
+        self.push_value(T::Item::default(), false)
+    }
+
+    pub fn push_option(&mut self, val: Option<T::Item>) -> Result<()> {
+        match val {
+            Some(val) => self.push(val),
+            None => self.push_null()
+        }
+    }
+
+    pub fn extend_from_slice(&mut self, vals: &[T::Item]) -> Result<()> {
+        self.data.values.reserve(mem::size_of_val(vals))?;
+        self.push_validity(vals.len(), true)?;
+        self.data.values.extend_from_slice(vals)?;
+        self.len += vals.len();
+        Ok(())
+    }
+
+    pub fn values(&self) -> &[T::Item] {
+        unsafe { self.data.values.typed_data() }
+    }
+
+    pub fn value(&self, i: usize) -> T::Item {
+        self.values()[i]
+    }
+
+    pub fn get(&self, i: usize) -> Option<T::Item> {
+        if self.is_valid(i) { Some(self.value(i)) } else { None }
     }
 
 }
 
 
-impl<T> Array<T> where T: PrimitiveType<Data=PrimitiveData<T>> {
+impl TypedArray<Boolean> {
+
+    fn push_value(&mut self, val: bool, valid: bool) -> Result<()> {
+        self.data.values.reserve(1)?;
+        self.push_validity(1, valid)?;
+        self.data.values.push(val)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn push(&mut self, val: bool) -> Result<()> {
+        self.push_value(val, true)
+    }
+
+    pub fn push_null(&mut self) -> Result<()> {
+        self.push_value(false, false)
+    }
+
+    pub fn push_option(&mut self, val: Option<bool>) -> Result<()> {
+        match val {
+            Some(val) => self.push(val),
+            None => self.push_null()
+        }
+    }
+
+    pub fn values(&self) -> &BitMap {
+        &self.data.values
+    }
+
+    pub fn value(&self, i: usize) -> bool {
+        self.data.values.get(i)
+    }
+
+    pub fn get(&self, i: usize) -> Option<bool> {
+        if self.is_valid(i) { Some(self.value(i)) } else { None }
+    }
+
+    pub fn iter(&self) -> BooleanIter<'_> {
+        BooleanIter { array: self, pos: 0 }
+    }
 
-    pub fn push(&mut self, val: T::Item) {    
-        if self.len == self.data.values.cap() {
-            self.data.values.double();
+    // number of valid, true slots
+    pub fn true_count(&self) -> usize {
+        match self.nulls {
+            Some(ref nulls) => {
+                bitmap::count_set_bits_and(self.data.values.as_bit_slice(), nulls.as_bit_slice())
+            },
+            None => self.data.values.count_set_bits()
         }
-        unsafe {
-            ptr::write(self.data.values.ptr().offset(self.len as isize), val);
+    }
+
+}
+
+
+pub struct BooleanIter<'a> {
+    array: &'a TypedArray<Boolean>,
+    pos: usize
+}
+
+
+impl<'a> Iterator for BooleanIter<'a> {
+    type Item = Option<bool>;
+
+    fn next(&mut self) -> Option<Option<bool>> {
+        if self.pos >= self.array.len {
+            return None;
         }
+        self.pos += 1;
+        Some(self.array.get(self.pos - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.array.len - self.pos;
+        (remaining, Some(remaining))
+    }
+}
+
+
+impl TypedArray<Decimal> {
+
+    pub fn precision(&self) -> i32 {
+        self.dtype.precision()
+    }
+
+    pub fn scale(&self) -> i32 {
+        self.dtype.scale()
+    }
+
+    fn push_value(&mut self, val: i128, valid: bool) -> Result<()> {
+        self.data.values.reserve(mem::size_of::<i128>())?;
+        self.push_validity(1, valid)?;
+        self.data.values.push(val)?;
         self.len += 1;
+        Ok(())
+    }
+
+    // checks the unscaled value against the precision
+    pub fn push(&mut self, val: i128) -> Result<()> {
+        self.dtype.validate(val)?;
+        self.push_value(val, true)
+    }
+
+    // for values validated by the caller, such as parsed or rescaled ones
+    fn push_unchecked(&mut self, val: i128) -> Result<()> {
+        self.push_value(val, true)
+    }
+
+    pub fn push_null(&mut self) -> Result<()> {
+        self.push_value(0, false)
+    }
+
+    pub fn push_option(&mut self, val: Option<i128>) -> Result<()> {
+        match val {
+            Some(val) => self.push(val),
+            None => self.push_null()
+        }
+    }
+
+    // all or nothing, the values are validated before any is appended
+    pub fn extend_from_slice(&mut self, vals: &[i128]) -> Result<()> {
+        for &val in vals {
+            self.dtype.validate(val)?;
+        }
+        self.data.values.reserve(mem::size_of_val(vals))?;
+        self.push_validity(vals.len(), true)?;
+        self.data.values.extend_from_slice(vals)?;
+        self.len += vals.len();
+        Ok(())
+    }
+
+    pub fn values(&self) -> &[i128] {
+        unsafe { self.data.values.typed_data() }
+    }
+
+    pub fn value(&self, i: usize) -> i128 {
+        self.values()[i]
+    }
+
+    pub fn get(&self, i: usize) -> Option<i128> {
+        if self.is_valid(i) { Some(self.value(i)) } else { None }
+    }
+
+    pub fn push_str(&mut self, val: &str) -> Result<()> {
+        let val = self.dtype.parse(val)?;
+        self.push_unchecked(val)
+    }
+
+    pub fn value_as_string(&self, i: usize) -> String {
+        self.dtype.format(self.value(i))
+    }
+
+    // copies into a new array of the given decimal type, nulls included
+    pub fn rescale(&self, to: Decimal) -> Result<Self> {
+        let mut result = Self::with_pool(to, self.pool.clone());
+        for i in 0..self.len {
+            match self.get(i) {
+                Some(val) => result.push_unchecked(self.dtype.rescale(val, &to)?)?,
+                None => result.push_null()?
+            }
+        }
+        Ok(result)
     }
 
 }
 
-// impl to_dtype static method for struct with arrow procedural macro
 
+impl TypedArray<Date32> {
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use dtypes::*;
+    pub fn value_as_date(&self, i: usize) -> Date {
+        self.dtype.to_date(self.value(i))
+    }
+
+}
 
-    #[test]
-    fn test_from_dtype() {
-        Array::new(Float32);
-        Array::new(Float64);
 
-        Array::new(Int8);
-        Array::new(Int16);
-        Array::new(Int32);
-        Array::new(Int64);
-        Array::new(UInt8);
-        Array::new(UInt16);
-        Array::new(UInt32);
-        Array::new(UInt64);
+impl TypedArray<Date64> {
 
-        Array::new(List(Int64));
-        Array::new(List(Float64));
+    pub fn value_as_date(&self, i: usize) -> Date {
+        self.dtype.to_date(self.value(i))
     }
 
-    #[test]
-    fn test_simple() {
-        let mut a = Array::new(Int64);
+}
 
-        assert_eq!(a.len(), 0);
-        assert_eq!(a.dtype(), Int64);
 
-        println!("{}", a.len());
-        println!("{}", a.data.values.cap());
+impl TypedArray<Time32> {
 
-        for i in 1..100 {
-            a.push(i);
+    pub fn value_as_time(&self, i: usize) -> Result<Time> {
+        self.dtype.to_time(self.value(i))
+    }
+
+}
+
+
+impl TypedArray<Time64> {
+
+    pub fn value_as_time(&self, i: usize) -> Result<Time> {
+        self.dtype.to_time(self.value(i))
+    }
+
+}
+
+
+impl TypedArray<Timestamp> {
+
+    pub fn timezone(&self) -> Option<&str> {
+        self.dtype.timezone()
+    }
+
+    pub fn value_as_datetime(&self, i: usize) -> DateTime {
+        self.dtype.to_datetime(self.value(i))
+    }
+
+}
+
+
+impl TypedArray<Struct> {
+
+    pub fn from_children(dtype: Struct, children: Vec<ArrayRef>,
+                         nulls: Option<BitMap>) -> Result<Self> {
+        Self::from_children_with_pool(dtype, children, nulls, default_pool())
+    }
+
+    // checks the children against the fields, the validity against their length
+    pub fn from_children_with_pool(dtype: Struct, children: Vec<ArrayRef>, nulls: Option<BitMap>,
+                                   pool: MemoryPoolRef) -> Result<Self> {
+        if children.len() != dtype.fields().len() {
+            return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "struct has {} fields, got {} children", dtype.fields().len(), children.len())));
+        }
+        let len = match children.first() {
+            Some(child) => child.len(),
+            None => nulls.as_ref().map_or(0, |nulls| nulls.len())
+        };
+        for (field, child) in dtype.fields().iter().zip(&children) {
+            if child.data_type() != *field.dtype() {
+                return Err(ArrowError::new(StatusCode::TypeError, format!(
+                    "child {} should be {}, got {}", field.name(), field.dtype(),
+                    child.data_type())));
+            }
+            if child.len() != len {
+                return Err(ArrowError::new(StatusCode::Invalid, format!(
+                    "child {} has length {}, expected {}", field.name(), child.len(), len)));
+            }
+            if !field.nullable() && child.null_count() > 0 {
+                return Err(ArrowError::new(StatusCode::Invalid, format!(
+                    "child {} is not nullable, but contains {} nulls", field.name(),
+                    child.null_count())));
+            }
+        }
+
+        let mut array = Self::with_pool(dtype, pool);
+        array.len = len;
+        array.set_nulls(nulls)?;
+        array.data.children = children;
+        Ok(array)
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.data.children.len()
+    }
+
+    pub fn columns(&self) -> &[ArrayRef] {
+        &self.data.children
+    }
+
+    pub fn column(&self, i: usize) -> &ArrayRef {
+        &self.data.children[i]
+    }
+
+    pub fn column_by_name(&self, name: &str) -> Option<&ArrayRef> {
+        self.dtype.index_of(name).map(|i| self.column(i))
+    }
+
+}
+
+
+impl TypedArray<Union> {
+
+    pub fn from_parts(dtype: Union, type_ids: &[i8], offsets: Option<&[i32]>,
+                      children: Vec<ArrayRef>) -> Result<Self> {
+        Self::from_parts_with_pool(dtype, type_ids, offsets, children, default_pool())
+    }
+
+    // offsets are required in dense mode and rejected in sparse mode
+    pub fn from_parts_with_pool(dtype: Union, type_ids: &[i8], offsets: Option<&[i32]>,
+                                children: Vec<ArrayRef>, pool: MemoryPoolRef) -> Result<Self> {
+        let invalid = |msg: String| Err(ArrowError::new(StatusCode::Invalid, msg));
+
+        if children.len() != dtype.fields().len() {
+            return invalid(format!("union has {} fields, got {} children",
+                                   dtype.fields().len(), children.len()));
+        }
+        for (field, child) in dtype.fields().iter().zip(&children) {
+            if child.data_type() != *field.dtype() {
+                return Err(ArrowError::new(StatusCode::TypeError, format!(
+                    "child {} should be {}, got {}", field.name(), field.dtype(),
+                    child.data_type())));
+            }
+            if dtype.mode() == UnionMode::Sparse && child.len() != type_ids.len() {
+                return invalid(format!("sparse child {} has length {}, expected {}",
+                                       field.name(), child.len(), type_ids.len()));
+            }
+        }
+        match (dtype.mode(), offsets) {
+            (UnionMode::Sparse, Some(_)) => return invalid("sparse union with offsets".to_string()),
+            (UnionMode::Dense, None) => return invalid("dense union without offsets".to_string()),
+            (UnionMode::Dense, Some(offsets)) if offsets.len() != type_ids.len() => {
+                return invalid(format!("{} offsets for {} type ids", offsets.len(),
+                                       type_ids.len()));
+            },
+            _ => {}
+        }
+        // offsets into each child must not decrease
+        let mut last_offsets = vec![0; children.len()];
+        for (i, &type_id) in type_ids.iter().enumerate() {
+            let child = match dtype.child_index(type_id) {
+                Some(child) => child,
+                None => return invalid(format!("unknown union type id {}", type_id))
+            };
+            if let Some(offsets) = offsets {
+                if offsets[i] < 0 || offsets[i] as usize >= children[child].len() {
+                    return invalid(format!("offset {} out of bounds of child {}", offsets[i],
+                                           child));
+                }
+                if offsets[i] < last_offsets[child] {
+                    return invalid(format!("offset {} of child {} is less than the previous {}",
+                                           offsets[i], child, last_offsets[child]));
+                }
+                last_offsets[child] = offsets[i];
+            }
+        }
+
+        let mut array = Self::with_pool(dtype, pool);
+        array.data.type_ids.extend_from_slice(type_ids)?;
+        if let Some(offsets) = offsets {
+            array.data.offsets.extend_from_slice(offsets)?;
+        }
+        array.data.children = children;
+        array.len = type_ids.len();
+        Ok(array)
+    }
+
+    pub fn mode(&self) -> UnionMode {
+        self.dtype.mode()
+    }
+
+    pub fn type_ids(&self) -> &[i8] {
+        unsafe { self.data.type_ids.typed_data() }
+    }
+
+    // empty in sparse mode
+    pub fn value_offsets(&self) -> &[i32] {
+        unsafe { self.data.offsets.typed_data() }
+    }
+
+    pub fn type_id(&self, i: usize) -> i8 {
+        self.type_ids()[i]
+    }
+
+    pub fn children(&self) -> &[ArrayRef] {
+        &self.data.children
+    }
+
+    pub fn child(&self, type_id: i8) -> Option<&ArrayRef> {
+        self.dtype.child_index(type_id).map(|i| &self.data.children[i])
+    }
+
+    pub fn value_offset(&self, i: usize) -> usize {
+        match self.mode() {
+            UnionMode::Sparse => {
+                assert!(i < self.len, "index {} out of bounds of array with length {}", i, self.len);
+                i
+            },
+            UnionMode::Dense => self.value_offsets()[i] as usize
+        }
+    }
+
+    // the active child of the slot and the index of its value there
+    pub fn value(&self, i: usize) -> (&ArrayRef, usize) {
+        let child = self.dtype.child_index(self.type_id(i)).expect("validated type id");
+        (&self.data.children[child], self.value_offset(i))
+    }
+
+}
+
+
+impl<K, V> TypedArray<Dictionary<K, V>>
+    where K: IndexType<Data=PrimitiveData<K>>,
+          V: DataType
+{
+
+    // an empty array whose keys point into the given, possibly shared values
+    // an empty array whose keys point into the given, possibly shared values
+    pub fn with_values(dtype: Dictionary<K, V>, values: Arc<TypedArray<V>>) -> Result<Self> {
+        if values.dtype.data_type() != dtype.1.data_type() {
+            return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "dictionary of {} got values of type {}", dtype.1.data_type(),
+                values.dtype.data_type())));
+        }
+        let pool = values.pool().clone();
+        let mut array = Self::with_pool(dtype, pool);
+        array.data.values = values;
+        Ok(array)
+    }
+
+    pub fn push_key(&mut self, key: K::Item) -> Result<()> {
+        match K::to_index(key) {
+            Some(index) if index < self.data.values.len() => {},
+            _ => return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "key out of bounds of dictionary with {} values", self.data.values.len())))
+        }
+        self.data.keys.reserve_items(1)?;
+        self.push_validity(1, true)?;
+        self.data.keys.push(key)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn push_null(&mut self) -> Result<()> {
+        self.data.keys.reserve_items(1)?;
+        self.push_validity(1, false)?;
+        self.data.keys.push(K::from_index(0).expect("zero is a valid key"))?;
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn keys(&self) -> &[K::Item] {
+        self.data.keys.values()
+    }
+
+    pub fn values(&self) -> &Arc<TypedArray<V>> {
+        &self.data.values
+    }
+
+    // index into the values, None for null slots
+    pub fn value_index(&self, i: usize) -> Option<usize> {
+        if self.is_valid(i) { K::to_index(self.keys()[i]) } else { None }
+    }
+
+}
+
+
+impl<T> TypedArray<T> where T: PrimitiveType<Data=PrimitiveData<T>> {
+
+    // makes room for additional more values, counted in items rather than bytes
+    fn reserve_items(&mut self, additional: usize) -> Result<()> {
+        self.data.values.reserve(additional * mem::size_of::<T::Item>())
+    }
+
+}
+
+
+// value types a dictionary can deduplicate, compared by their bytes
+pub trait DictionaryValue: DataType {
+    type Value: ?Sized;
+
+    fn value_bytes(value: &Self::Value) -> &[u8];
+    fn push_value(array: &mut TypedArray<Self>, value: &Self::Value) -> Result<()>;
+}
+
+
+macro_rules! dictionary_value {
+    ($DT:ty, $T:ty) => (
+        impl DictionaryValue for $DT {
+            type Value = $T;
+
+            fn value_bytes(value: &$T) -> &[u8] {
+                unsafe { slice::from_raw_parts(value as *const $T as *const u8, mem::size_of::<$T>()) }
+            }
+
+            fn push_value(array: &mut TypedArray<Self>, value: &$T) -> Result<()> {
+                array.push(*value)
+            }
+        }
+    )
+}
+
+
+dictionary_value!(Int8, i8);
+dictionary_value!(Int16, i16);
+dictionary_value!(Int32, i32);
+dictionary_value!(Int64, i64);
+dictionary_value!(UInt8, u8);
+dictionary_value!(UInt16, u16);
+dictionary_value!(UInt32, u32);
+dictionary_value!(UInt64, u64);
+dictionary_value!(HalfFloat, f16);
+dictionary_value!(Float32, f32);
+dictionary_value!(Float64, f64);
+
+
+impl DictionaryValue for Utf8 {
+    type Value = str;
+
+    fn value_bytes(value: &str) -> &[u8] {
+        value.as_bytes()
+    }
+
+    fn push_value(array: &mut TypedArray<Self>, value: &str) -> Result<()> {
+        array.push(value)
+    }
+}
+
+
+impl DictionaryValue for Binary {
+    type Value = [u8];
+
+    fn value_bytes(value: &[u8]) -> &[u8] {
+        value
+    }
+
+    fn push_value(array: &mut TypedArray<Self>, value: &[u8]) -> Result<()> {
+        array.push(value)
+    }
+}
+
+
+// encodes values as they are pushed, each distinct value is stored once
+pub struct DictionaryBuilder<K: IndexType, V: DictionaryValue> {
+    array: TypedArray<Dictionary<K, V>>,
+    values: TypedArray<V>,
+    lookup: HashMap<Box<[u8]>, K::Item>
+}
+
+
+impl<K, V> DictionaryBuilder<K, V>
+    where K: IndexType<Data=PrimitiveData<K>>,
+          V: DictionaryValue
+{
+
+    pub fn new(dtype: Dictionary<K, V>) -> Self {
+        Self::with_pool(dtype, default_pool())
+    }
+
+    pub fn with_pool(dtype: Dictionary<K, V>, pool: MemoryPoolRef) -> Self {
+        DictionaryBuilder {
+            values: TypedArray::with_pool(dtype.1.clone(), pool.clone()),
+            array: TypedArray::with_pool(dtype, pool),
+            lookup: HashMap::new()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.array.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.array.is_empty()
+    }
+
+    // number of distinct values seen so far
+    pub fn num_values(&self) -> usize {
+        self.values.len()
+    }
+
+    // returns the key of the value
+    pub fn push(&mut self, value: &V::Value) -> Result<K::Item> {
+        let bytes = V::value_bytes(value);
+        let key = match self.lookup.get(bytes) {
+            Some(&key) => key,
+            None => {
+                let index = self.values.len();
+                let key = match K::from_index(index) {
+                    Some(key) => key,
+                    None => return Err(ArrowError::new(StatusCode::Invalid, format!(
+                        "dictionary of {} values overflows its index type", index + 1)))
+                };
+                V::push_value(&mut self.values, value)?;
+                self.lookup.insert(bytes.into(), key);
+                key
+            }
+        };
+        self.array.data.keys.reserve_items(1)?;
+        self.array.push_validity(1, true)?;
+        self.array.data.keys.push(key)?;
+        self.array.len += 1;
+        Ok(key)
+    }
+
+    pub fn push_null(&mut self) -> Result<()> {
+        self.array.push_null()
+    }
+
+    pub fn finish(self) -> TypedArray<Dictionary<K, V>> {
+        let mut array = self.array;
+        array.data.values = Arc::new(self.values);
+        array
+    }
+
+}
+
+
+impl TypedArray<Map> {
+
+    pub fn from_parts(dtype: Map, offsets: &[i32], keys: ArrayRef, items: ArrayRef,
+                      nulls: Option<BitMap>) -> Result<Self> {
+        Self::from_parts_with_pool(dtype, offsets, keys, items, nulls, default_pool())
+    }
+
+    // the entries are validated like a struct, so null keys are rejected
+    pub fn from_parts_with_pool(dtype: Map, offsets: &[i32], keys: ArrayRef, items: ArrayRef,
+                                nulls: Option<BitMap>, pool: MemoryPoolRef) -> Result<Self> {
+        let entries = StructArray::from_children_with_pool(dtype.entries(), vec![keys, items],
+                                                           None, pool.clone())?;
+        validate_offsets(offsets, entries.len())?;
+
+        let mut array = Self::with_pool(dtype, pool);
+        array.data.offsets.extend_from_slice(offsets)?;
+        array.data.entries = entries;
+        array.len = offsets.len().saturating_sub(1);
+        array.set_nulls(nulls)?;
+        Ok(array)
+    }
+
+    pub fn entries(&self) -> &StructArray {
+        &self.data.entries
+    }
+
+    pub fn keys(&self) -> &ArrayRef {
+        self.data.entries.column(0)
+    }
+
+    pub fn items(&self) -> &ArrayRef {
+        self.data.entries.column(1)
+    }
+
+    // iterates the (key, item) pairs of a slot, K and V being the concrete child arrays
+    pub fn iter_row<'a, K, V>(&'a self, i: usize) -> Result<MapRow<'a, K, V>>
+        where K: ValueAccessor<'a> + 'static,
+              V: ValueAccessor<'a> + 'static
+    {
+        let keys = self.keys().as_any().downcast_ref::<K>();
+        let items = self.items().as_any().downcast_ref::<V>();
+        match (keys, items) {
+            (Some(keys), Some(items)) => Ok(MapRow { keys, items, range: self.value_range(i) }),
+            _ => Err(ArrowError::new(StatusCode::TypeError, format!(
+                "map entries are {} and {}", self.keys().data_type(), self.items().data_type())))
+        }
+    }
+
+}
+
+
+// typed access to a slot of a concrete array
+pub trait ValueAccessor<'a>: Array {
+    type Item;
+
+    fn value_at(&'a self, i: usize) -> Self::Item;
+}
+
+
+macro_rules! value_accessor {
+    ($DT:ty, $T:ty) => (
+        impl<'a> ValueAccessor<'a> for TypedArray<$DT> {
+            type Item = $T;
+
+            fn value_at(&'a self, i: usize) -> $T {
+                self.value(i)
+            }
+        }
+    )
+}
+
+
+value_accessor!(Boolean, bool);
+value_accessor!(Int8, i8);
+value_accessor!(Int16, i16);
+value_accessor!(Int32, i32);
+value_accessor!(Int64, i64);
+value_accessor!(UInt8, u8);
+value_accessor!(UInt16, u16);
+value_accessor!(UInt32, u32);
+value_accessor!(UInt64, u64);
+value_accessor!(HalfFloat, f16);
+value_accessor!(Float32, f32);
+value_accessor!(Float64, f64);
+value_accessor!(Utf8, &'a str);
+value_accessor!(Binary, &'a [u8]);
+value_accessor!(LargeUtf8, &'a str);
+value_accessor!(LargeBinary, &'a [u8]);
+value_accessor!(FixedSizeBinary, &'a [u8]);
+
+
+pub struct MapRow<'a, K: 'a, V: 'a> {
+    keys: &'a K,
+    items: &'a V,
+    range: Range<usize>
+}
+
+
+impl<'a, K, V> Iterator for MapRow<'a, K, V>
+    where K: ValueAccessor<'a>,
+          V: ValueAccessor<'a>
+{
+    type Item = (K::Item, Option<V::Item>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let j = self.range.next()?;
+        let item = if self.items.is_valid(j) { Some(self.items.value_at(j)) } else { None };
+        Some((self.keys.value_at(j), item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+
+// offsets must start at zero, never decrease and stay within the values
+fn validate_offsets<O: OffsetSize>(offsets: &[O], values: usize) -> Result<()> {
+    let valid = offsets.first().is_none_or(|&first| first.to_usize() == 0) &&
+        offsets.windows(2).all(|w| w[0] <= w[1]) &&
+        offsets.last().is_none_or(|&last| last.to_usize() <= values);
+    if valid {
+        Ok(())
+    } else {
+        Err(ArrowError::new(StatusCode::Invalid, "offsets are not monotonic or out of bounds"))
+    }
+}
+
+
+impl<T> TypedArray<T> where T: DataType, T::Data: OffsetsData {
+
+    pub fn value_offsets(&self) -> &[<T::Data as OffsetsData>::Offset] {
+        unsafe { self.data.offsets().typed_data() }
+    }
+
+    // positions of the slot's values in the child or value data
+    pub fn value_range(&self, i: usize) -> Range<usize> {
+        assert!(i < self.len, "index {} out of bounds of array with length {}", i, self.len);
+        let offsets = self.value_offsets();
+        offsets[i].to_usize()..offsets[i + 1].to_usize()
+    }
+
+    pub fn value_length(&self, i: usize) -> usize {
+        self.value_range(i).len()
+    }
+
+    // closes a slot ending at end, the caller appends the values after this succeeded
+    fn push_offset(&mut self, end: usize, valid: bool) -> Result<()> {
+        let offset = match <T::Data as OffsetsData>::Offset::from_usize(end) {
+            Some(offset) => offset,
+            None => return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "{} values overflow the offsets of {}", end, self.dtype.name())))
+        };
+        let first = self.data.offsets().is_empty();
+        let size = mem::size_of::<<T::Data as OffsetsData>::Offset>();
+        self.data.offsets_mut().reserve(if first { 2 } else { 1 } * size)?;
+        self.push_validity(1, valid)?;
+        if first {
+            let zero = <T::Data as OffsetsData>::Offset::from_usize(0).expect("zero offset");
+            self.data.offsets_mut().push(zero)?;
+        }
+        self.data.offsets_mut().push(offset)?;
+        self.len += 1;
+        Ok(())
+    }
+
+}
+
+
+impl<T, O> TypedArray<T> where T: DataType<Data=BinaryData<O>>, O: OffsetSize {
+
+    // copies already validated parts into the array's own buffers
+    fn with_parts(dtype: T, offsets: &[O], values: &[u8], pool: MemoryPoolRef) -> Result<Self> {
+        validate_offsets(offsets, values.len())?;
+        let mut array = Self::with_pool(dtype, pool);
+        if offsets.len() > 1 {
+            array.data.offsets.extend_from_slice(offsets)?;
+            array.data.values.extend_from_slice(&values[..offsets[offsets.len() - 1].to_usize()])?;
+            array.len = offsets.len() - 1;
+        }
+        Ok(array)
+    }
+
+    fn push_bytes_unchecked(&mut self, val: &[u8], valid: bool) -> Result<()> {
+        self.data.values.reserve(val.len())?;
+        let end = self.data.values.len() + val.len();
+        self.push_offset(end, valid)?;
+        self.data.values.extend_from_slice(val)
+    }
+
+    pub fn value_data(&self) -> &[u8] {
+        self.data.values.as_slice()
+    }
+
+    fn value_bytes(&self, i: usize) -> &[u8] {
+        &self.value_data()[self.value_range(i)]
+    }
+
+}
+
+
+macro_rules! string_array {
+    ($DT:ident, $O:ty) => (
+        impl TypedArray<$DT> {
+
+            // validates the offsets and that the values are utf-8
+            pub fn from_parts(offsets: &[$O], values: &[u8]) -> Result<Self> {
+                Self::from_parts_with_pool(offsets, values, default_pool())
+            }
+
+            pub fn from_parts_with_pool(offsets: &[$O], values: &[u8],
+                                        pool: MemoryPoolRef) -> Result<Self> {
+                validate_offsets(offsets, values.len())?;
+                if let Err(e) = str::from_utf8(values) {
+                    return Err(ArrowError::new(StatusCode::Invalid, format!("invalid utf-8: {}", e)));
+                }
+                for &offset in offsets {
+                    // a value must not start or end in the middle of a code point
+                    let offset = offset.to_usize();
+                    if offset < values.len() && (values[offset] as i8) < -0x40 {
+                        return Err(ArrowError::new(StatusCode::Invalid, format!(
+                            "offset {} splits a utf-8 code point", offset)));
+                    }
+                }
+                Self::with_parts($DT, offsets, values, pool)
+            }
+
+            pub fn push(&mut self, val: &str) -> Result<()> {
+                self.push_bytes_unchecked(val.as_bytes(), true)
+            }
+
+            pub fn push_bytes(&mut self, val: &[u8]) -> Result<()> {
+                match str::from_utf8(val) {
+                    Ok(val) => self.push(val),
+                    Err(e) => Err(ArrowError::new(StatusCode::Invalid, format!("invalid utf-8: {}", e)))
+                }
+            }
+
+            // null slots are empty
+            pub fn push_null(&mut self) -> Result<()> {
+                self.push_bytes_unchecked(&[], false)
+            }
+
+            pub fn push_option(&mut self, val: Option<&str>) -> Result<()> {
+                match val {
+                    Some(val) => self.push(val),
+                    None => self.push_null()
+                }
+            }
+
+            pub fn value(&self, i: usize) -> &str {
+                // every value was validated on the way in
+                unsafe { str::from_utf8_unchecked(self.value_bytes(i)) }
+            }
+
+            pub fn get(&self, i: usize) -> Option<&str> {
+                if self.is_valid(i) { Some(self.value(i)) } else { None }
+            }
+
+        }
+    )
+}
+
+
+macro_rules! binary_array {
+    ($DT:ident, $O:ty) => (
+        impl TypedArray<$DT> {
+
+            pub fn from_parts(offsets: &[$O], values: &[u8]) -> Result<Self> {
+                Self::from_parts_with_pool(offsets, values, default_pool())
+            }
+
+            pub fn from_parts_with_pool(offsets: &[$O], values: &[u8],
+                                        pool: MemoryPoolRef) -> Result<Self> {
+                Self::with_parts($DT, offsets, values, pool)
+            }
+
+            pub fn push(&mut self, val: &[u8]) -> Result<()> {
+                self.push_bytes_unchecked(val, true)
+            }
+
+            // null slots are empty
+            pub fn push_null(&mut self) -> Result<()> {
+                self.push_bytes_unchecked(&[], false)
+            }
+
+            pub fn push_option(&mut self, val: Option<&[u8]>) -> Result<()> {
+                match val {
+                    Some(val) => self.push(val),
+                    None => self.push_null()
+                }
+            }
+
+            pub fn value(&self, i: usize) -> &[u8] {
+                self.value_bytes(i)
+            }
+
+            pub fn get(&self, i: usize) -> Option<&[u8]> {
+                if self.is_valid(i) { Some(self.value(i)) } else { None }
+            }
+
+        }
+    )
+}
+
+
+string_array!(Utf8, i32);
+string_array!(LargeUtf8, i64);
+binary_array!(Binary, i32);
+binary_array!(LargeBinary, i64);
+
+
+macro_rules! list_array {
+    ($DT:ident) => (
+        impl<T: DataType> TypedArray<$DT<T>> {
+
+            pub fn values(&self) -> &TypedArray<T> {
+                &self.data.values
+            }
+
+            // the child to append the values of the next slot to, before closing it with append
+            pub fn values_mut(&mut self) -> &mut TypedArray<T> {
+                &mut self.data.values
+            }
+
+            // closes a slot holding the child values appended since the previous one
+            pub fn append(&mut self, valid: bool) -> Result<()> {
+                let end = self.data.values.len();
+                self.push_offset(end, valid)
+            }
+
+            // null slots are empty
+            pub fn push_null(&mut self) -> Result<()> {
+                self.append(false)
+            }
+
+        }
+    )
+}
+
+
+list_array!(List);
+list_array!(LargeList);
+
+
+impl TypedArray<FixedSizeBinary> {
+
+    fn push_value(&mut self, val: &[u8], valid: bool) -> Result<()> {
+        self.data.values.reserve(val.len())?;
+        self.push_validity(1, valid)?;
+        self.data.values.extend_from_slice(val)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn byte_width(&self) -> usize {
+        self.dtype.byte_width()
+    }
+
+    pub fn push(&mut self, val: &[u8]) -> Result<()> {
+        if val.len() != self.byte_width() {
+            return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "value of {} bytes pushed to fixed size binary of width {}",
+                val.len(), self.byte_width())));
+        }
+        self.push_value(val, true)
+    }
+
+    // null slots are zeroed
+    pub fn push_null(&mut self) -> Result<()> {
+        let zeros = vec![0u8; self.byte_width()];
+        self.push_value(&zeros, false)
+    }
+
+    pub fn push_option(&mut self, val: Option<&[u8]>) -> Result<()> {
+        match val {
+            Some(val) => self.push(val),
+            None => self.push_null()
+        }
+    }
+
+    pub fn value_data(&self) -> &[u8] {
+        self.data.values.as_slice()
+    }
+
+    pub fn value(&self, i: usize) -> &[u8] {
+        assert!(i < self.len, "index {} out of bounds of array with length {}", i, self.len);
+        let width = self.byte_width();
+        &self.value_data()[i * width..(i + 1) * width]
+    }
+
+    pub fn get(&self, i: usize) -> Option<&[u8]> {
+        if self.is_valid(i) { Some(self.value(i)) } else { None }
+    }
+
+}
+
+
+impl<T: DataType> TypedArray<FixedSizeList<T>> {
+
+    // takes over a flat child array, which must hold list_size values per slot
+    pub fn from_values(dtype: FixedSizeList<T>, values: TypedArray<T>,
+                       nulls: Option<BitMap>) -> Result<Self> {
+        let size = dtype.list_size();
+        let len = match size {
+            // the length cannot be derived from the values
+            0 => match nulls {
+                Some(ref nulls) => nulls.len(),
+                None => return Err(ArrowError::new(StatusCode::Invalid,
+                    "lists of size 0 need a validity bitmap to know their length".to_string()))
+            },
+            _ if values.len().is_multiple_of(size) => values.len() / size,
+            _ => return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "{} values do not split into lists of {}", values.len(), size)))
+        };
+        let pool = values.pool().clone();
+        let mut array = Self::with_pool(dtype, pool);
+        array.len = len;
+        array.set_nulls(nulls)?;
+        array.data.values = values;
+        Ok(array)
+    }
+
+    pub fn into_values(self) -> TypedArray<T> {
+        self.data.values
+    }
+
+    pub fn list_size(&self) -> usize {
+        self.dtype.list_size()
+    }
+
+    pub fn values(&self) -> &TypedArray<T> {
+        &self.data.values
+    }
+
+    // the child to append list_size values to, before closing the slot with append
+    pub fn values_mut(&mut self) -> &mut TypedArray<T> {
+        &mut self.data.values
+    }
+
+    pub fn append(&mut self, valid: bool) -> Result<()> {
+        let expected = (self.len + 1) * self.list_size();
+        if self.data.values.len() != expected {
+            return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "fixed size list needs {} child values, got {}", expected,
+                self.data.values.len())));
+        }
+        self.push_validity(1, valid)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn value_range(&self, i: usize) -> Range<usize> {
+        assert!(i < self.len, "index {} out of bounds of array with length {}", i, self.len);
+        i * self.list_size()..(i + 1) * self.list_size()
+    }
+
+}
+
+
+impl<T> TypedArray<FixedSizeList<T>> where T: PrimitiveType<Data=PrimitiveData<T>> {
+
+    pub fn push(&mut self, val: &[T::Item]) -> Result<()> {
+        if val.len() != self.list_size() {
+            return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "slice of {} values pushed to fixed size list of {}", val.len(),
+                self.list_size())));
+        }
+        self.data.values.reserve_items(val.len())?;
+        self.data.values.extend_from_slice(val)?;
+        self.append(true)
+    }
+
+    // null slots hold zeroed values
+    pub fn push_null(&mut self) -> Result<()> {
+        self.data.values.reserve_items(self.list_size())?;
+        for _ in 0..self.list_size() {
+            self.data.values.push(T::Item::default())?;
+        }
+        self.append(false)
+    }
+
+    pub fn push_option(&mut self, val: Option<&[T::Item]>) -> Result<()> {
+        match val {
+            Some(val) => self.push(val),
+            None => self.push_null()
+        }
+    }
+
+    pub fn value(&self, i: usize) -> &[T::Item] {
+        &self.data.values.values()[self.value_range(i)]
+    }
+
+    pub fn get(&self, i: usize) -> Option<&[T::Item]> {
+        if self.is_valid(i) { Some(self.value(i)) } else { None }
+    }
+
+}
+
+
+impl<T> Array for TypedArray<T>
+    where T: DataType + Send + Sync + 'static,
+          T::Data: Send + Sync + 'static
+{
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn data_type(&self) -> types::DataType {
+        self.dtype.data_type()
+    }
+
+    fn null_count(&self) -> usize {
+        self.null_count
+    }
+
+    fn is_null(&self, i: usize) -> bool {
+        TypedArray::is_null(self, i)
+    }
+
+}
+
+
+// all slots are null, so there is nothing to allocate
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct NullArray {
+    len: usize
+}
+
+
+impl NullArray {
+
+    pub fn new(len: usize) -> Self {
+        NullArray { len }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_null(&mut self) {
+        self.len += 1;
+    }
+
+    pub fn append_nulls(&mut self, n: usize) {
+        self.len += n;
+    }
+
+    pub fn slice(&self, offset: usize, len: usize) -> NullArray {
+        assert!(offset + len <= self.len, "slice [{}, {}) out of bounds of array with length {}",
+                offset, offset + len, self.len);
+        NullArray::new(len)
+    }
+
+}
+
+
+impl Array for NullArray {
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn data_type(&self) -> types::DataType {
+        types::DataType::NA
+    }
+
+    fn null_count(&self) -> usize {
+        self.len
+    }
+
+    fn is_null(&self, i: usize) -> bool {
+        assert!(i < self.len, "index {} out of bounds of array with length {}", i, self.len);
+        true
+    }
+
+}
+
+
+pub type BooleanArray = TypedArray<Boolean>;
+pub type Int8Array = TypedArray<Int8>;
+pub type Int16Array = TypedArray<Int16>;
+pub type Int32Array = TypedArray<Int32>;
+pub type Int64Array = TypedArray<Int64>;
+pub type UInt8Array = TypedArray<UInt8>;
+pub type UInt16Array = TypedArray<UInt16>;
+pub type UInt32Array = TypedArray<UInt32>;
+pub type UInt64Array = TypedArray<UInt64>;
+pub type Float16Array = TypedArray<HalfFloat>;
+pub type Float32Array = TypedArray<Float32>;
+pub type Float64Array = TypedArray<Float64>;
+pub type DecimalArray = TypedArray<Decimal>;
+pub type Date32Array = TypedArray<Date32>;
+pub type Date64Array = TypedArray<Date64>;
+pub type Time32Array = TypedArray<Time32>;
+pub type Time64Array = TypedArray<Time64>;
+pub type TimestampArray = TypedArray<Timestamp>;
+pub type IntervalYearMonthArray = TypedArray<IntervalYearMonth>;
+pub type IntervalDayTimeArray = TypedArray<IntervalDayTime>;
+pub type StringArray = TypedArray<Utf8>;
+pub type BinaryArray = TypedArray<Binary>;
+pub type LargeStringArray = TypedArray<LargeUtf8>;
+pub type LargeBinaryArray = TypedArray<LargeBinary>;
+pub type FixedSizeBinaryArray = TypedArray<FixedSizeBinary>;
+pub type ListArray<T> = TypedArray<List<T>>;
+pub type LargeListArray<T> = TypedArray<LargeList<T>>;
+pub type FixedSizeListArray<T> = TypedArray<FixedSizeList<T>>;
+pub type StructArray = TypedArray<Struct>;
+pub type UnionArray = TypedArray<Union>;
+pub type DictionaryArray<K, V> = TypedArray<Dictionary<K, V>>;
+pub type MapArray = TypedArray<Map>;
+
+// impl to_dtype static method for struct with arrow procedural macro
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use errors::StatusCode;
+    use mempool::{MemoryPool, DefaultMemoryPool, CheckedMemoryPool};
+
+    #[test]
+    fn test_from_dtype() {
+        TypedArray::new(HalfFloat);
+        TypedArray::new(Float32);
+        TypedArray::new(Float64);
+
+        TypedArray::new(Int8);
+        TypedArray::new(Int16);
+        TypedArray::new(Int32);
+        TypedArray::new(Int64);
+        TypedArray::new(UInt8);
+        TypedArray::new(UInt16);
+        TypedArray::new(UInt32);
+        TypedArray::new(UInt64);
+
+        TypedArray::new(Boolean);
+        TypedArray::new(Decimal::new(10, 2).unwrap());
+        TypedArray::new(Date32::new(DateUnit::Day).unwrap());
+        TypedArray::new(Date64::new(DateUnit::Milli).unwrap());
+        TypedArray::new(Time32::new(TimeUnit::Second).unwrap());
+        TypedArray::new(Time64::new(TimeUnit::Nano).unwrap());
+        TypedArray::new(Timestamp::new(TimeUnit::Micro, None));
+        TypedArray::new(IntervalYearMonth);
+        TypedArray::new(IntervalDayTime);
+        TypedArray::new(Utf8);
+        TypedArray::new(Binary);
+        TypedArray::new(FixedSizeBinary::new(16).unwrap());
+
+        TypedArray::new(List(Int64));
+        TypedArray::new(List(Float64));
+        TypedArray::new(LargeList(Utf8));
+        TypedArray::new(FixedSizeList::new(Float32, 3).unwrap());
+        TypedArray::new(LargeUtf8);
+        TypedArray::new(LargeBinary);
+        TypedArray::new(Struct::new(vec![]));
+        TypedArray::new(Dictionary(Int32, Utf8));
+    }
+
+    #[test]
+    fn test_simple() {
+        let mut a = TypedArray::new(Int64);
+
+        assert_eq!(a.len(), 0);
+        assert_eq!(a.dtype(), Int64);
+
+        println!("{}", a.len());
+        println!("{}", a.data.values.capacity());
+
+        for i in 1..100 {
+            a.push(i).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_allocates_through_pool() {
+        let pool = Arc::new(DefaultMemoryPool::new());
+        {
+            let mut a = TypedArray::with_pool(Int32, pool.clone());
+            for i in 0..10 {
+                a.push(i).unwrap();
+            }
+            assert_eq!(a.len(), 10);
+            assert_eq!(pool.bytes_allocated(), a.data.values.capacity());
+        }
+        assert_eq!(pool.bytes_allocated(), 0);
+        assert!(pool.max_memory() > 0);
+    }
+
+    #[test]
+    fn test_extend_from_slice() {
+        let mut a = TypedArray::new(UInt16);
+        a.push(1).unwrap();
+        a.extend_from_slice(&[2, 3, 4]).unwrap();
+        a.extend_from_slice(&[]).unwrap();
+        assert_eq!(a.len(), 4);
+        assert_eq!(a.values(), &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_nulls() {
+        let mut a = TypedArray::new(Int32);
+        a.push(1).unwrap();
+        a.push(2).unwrap();
+        assert!(a.nulls().is_none());
+        assert_eq!(a.null_count(), 0);
+        assert!(a.is_valid(1));
+
+        a.push_null().unwrap();
+        a.push_option(Some(4)).unwrap();
+        a.push_option(None).unwrap();
+        a.extend_from_slice(&[6, 7]).unwrap();
+
+        assert_eq!(a.len(), 7);
+        assert_eq!(a.null_count(), 2);
+        assert_eq!(a.nulls().unwrap().len(), 7);
+        assert_eq!(a.nulls().unwrap().count_set_bits(), 5);
+        assert!(a.is_valid(0));
+        assert!(a.is_null(2));
+        assert!(a.is_null(4));
+        assert!(a.is_valid(6));
+        assert_eq!(a.values(), &[1, 2, 0, 4, 0, 6, 7]);
+
+        let values: Vec<Option<i32>> = (0..a.len()).map(|i| a.get(i)).collect();
+        assert_eq!(values, vec![Some(1), Some(2), None, Some(4), None, Some(6), Some(7)]);
+    }
+
+    #[test]
+    fn test_null_first() {
+        let mut a = TypedArray::new(Float64);
+        a.push_null().unwrap();
+        a.push(1.5).unwrap();
+        assert_eq!(a.null_count(), 1);
+        assert!(a.is_null(0));
+        assert_eq!(a.get(1), Some(1.5));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_is_null_out_of_bounds() {
+        let mut a = TypedArray::new(Int8);
+        a.push(1).unwrap();
+        a.is_null(1);
+    }
+
+    #[test]
+    fn test_push_null_exceeding_pool_limit() {
+        let pool = Arc::new(DefaultMemoryPool::with_limit(64));
+        let mut a = TypedArray::with_pool(Int64, pool.clone());
+        for i in 0..8 {
+            a.push(i).unwrap();
+        }
+        assert_eq!(a.push_null().unwrap_err().code(), StatusCode::OutOfMemory);
+        assert_eq!(a.len(), 8);
+        assert_eq!(a.null_count(), 0);
+        assert!(a.nulls().is_none());
+    }
+
+    #[test]
+    fn test_boolean_array() {
+        let mut a = BooleanArray::new(Boolean);
+        for i in 0..100 {
+            a.push(i % 3 == 0).unwrap();
+        }
+        assert_eq!(a.len(), 100);
+        assert_eq!(a.null_count(), 0);
+        assert!(a.value(0));
+        assert!(!a.value(1));
+        assert_eq!(a.true_count(), 34);
+        assert_eq!(a.values().len(), 100);
+    }
+
+    #[test]
+    fn test_boolean_array_nulls() {
+        let mut a = BooleanArray::new(Boolean);
+        a.push(true).unwrap();
+        a.push_null().unwrap();
+        a.push_option(Some(false)).unwrap();
+        a.push_option(None).unwrap();
+        a.push(true).unwrap();
+
+        assert_eq!(a.null_count(), 2);
+        assert!(a.is_null(1));
+        assert_eq!(a.true_count(), 2);
+        assert_eq!(a.iter().collect::<Vec<_>>(),
+                   vec![Some(true), None, Some(false), None, Some(true)]);
+        assert_eq!(a.iter().size_hint(), (5, Some(5)));
+
+        let dynamic: ArrayRef = Arc::new(a);
+        assert_eq!(dynamic.data_type(), types::DataType::Bool);
+        assert_eq!(dynamic.null_count(), 2);
+    }
+
+    #[test]
+    fn test_string_array() {
+        let mut a = StringArray::new(Utf8);
+        a.push("hello").unwrap();
+        a.push("").unwrap();
+        a.push_null().unwrap();
+        a.push_option(Some("wörld")).unwrap();
+
+        assert_eq!(a.len(), 4);
+        assert_eq!(a.null_count(), 1);
+        assert_eq!(a.value(0), "hello");
+        assert_eq!(a.value(1), "");
+        assert_eq!(a.get(2), None);
+        assert_eq!(a.get(3), Some("wörld"));
+        assert_eq!(a.value_offsets(), &[0, 5, 5, 5, 11]);
+        assert_eq!(a.value_length(3), 6);
+        assert_eq!(a.value_data(), "hellowörld".as_bytes());
+
+        let dynamic: ArrayRef = Arc::new(a);
+        assert_eq!(dynamic.data_type(), types::DataType::String);
+    }
+
+    #[test]
+    fn test_string_array_validates_utf8() {
+        let mut a = StringArray::new(Utf8);
+        a.push_bytes(b"ok").unwrap();
+        assert_eq!(a.push_bytes(&[0xff, 0xfe]).unwrap_err().code(), StatusCode::Invalid);
+        assert_eq!(a.len(), 1);
+
+        let pool = default_pool();
+        let a = StringArray::from_parts_with_pool(&[0, 1, 3], b"abc", pool.clone()).unwrap();
+        assert_eq!(a.value(1), "bc");
+
+        let err = StringArray::from_parts(&[0, 2], &[0xc3, 0x28]).err().unwrap();
+        assert_eq!(err.code(), StatusCode::Invalid);
+        // splits the two byte encoding of ö
+        let err = StringArray::from_parts(&[0, 1, 2], "ö".as_bytes()).err().unwrap();
+        assert_eq!(err.code(), StatusCode::Invalid);
+        let err = StringArray::from_parts(&[0, 3, 2], b"abc").err().unwrap();
+        assert_eq!(err.code(), StatusCode::Invalid);
+        let err = StringArray::from_parts(&[0, 4], b"abc").err().unwrap();
+        assert_eq!(err.code(), StatusCode::Invalid);
+    }
+
+    #[test]
+    fn test_binary_array() {
+        let mut a = BinaryArray::new(Binary);
+        a.push(&[1, 2, 3]).unwrap();
+        a.push_option(None).unwrap();
+        a.push(&[0xff]).unwrap();
+
+        assert_eq!(a.value(0), &[1, 2, 3]);
+        assert_eq!(a.get(1), None);
+        assert_eq!(a.value(2), &[0xff]);
+        assert_eq!(a.null_count(), 1);
+
+        let b = BinaryArray::from_parts(a.value_offsets(), a.value_data()).unwrap();
+        assert_eq!(b.len(), 3);
+        assert_eq!(b.value(2), &[0xff]);
+    }
+
+    #[test]
+    fn test_fixed_size_binary_array() {
+        assert_eq!(FixedSizeBinary::new(-1).unwrap_err().code(), StatusCode::Invalid);
+        let dtype = FixedSizeBinary::new(4).unwrap();
+        assert_eq!(dtype.width(), 4);
+        let mut a = FixedSizeBinaryArray::new(dtype);
+        a.push(&[1, 2, 3, 4]).unwrap();
+        a.push_null().unwrap();
+        a.push_option(Some(&[5, 6, 7, 8])).unwrap();
+
+        let err = a.push(&[1, 2, 3]).unwrap_err();
+        assert_eq!(err.code(), StatusCode::Invalid);
+
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.byte_width(), 4);
+        assert_eq!(a.null_count(), 1);
+        assert_eq!(a.value(0), &[1, 2, 3, 4]);
+        assert_eq!(a.get(1), None);
+        assert_eq!(a.value(1), &[0, 0, 0, 0]);
+        assert_eq!(a.value(2), &[5, 6, 7, 8]);
+        assert_eq!(a.value_data().len(), 12);
+
+        let dynamic: ArrayRef = Arc::new(a);
+        assert_eq!(dynamic.data_type(), types::DataType::FixedSizedBinary(4));
+        assert_eq!(dynamic.data_type().bit_width() as usize, dtype.bits());
+    }
+
+    #[test]
+    fn test_decimal_type() {
+        assert!(Decimal::new(38, 10).is_ok());
+        assert!(Decimal::new(5, 5).is_ok());
+        assert_eq!(Decimal::new(0, 0).unwrap_err().code(), StatusCode::Invalid);
+        assert_eq!(Decimal::new(39, 0).unwrap_err().code(), StatusCode::Invalid);
+        assert_eq!(Decimal::new(5, 6).unwrap_err().code(), StatusCode::Invalid);
+        assert_eq!(Decimal::new(5, -1).unwrap_err().code(), StatusCode::Invalid);
+
+        let dtype = Decimal::new(5, 2).unwrap();
+        assert_eq!(dtype.parse("123.45").unwrap(), 12345);
+        assert_eq!(dtype.parse("-1.5").unwrap(), -150);
+        assert_eq!(dtype.parse("+7").unwrap(), 700);
+        assert_eq!(dtype.parse(".05").unwrap(), 5);
+        assert_eq!(dtype.parse("999.99").unwrap(), 99999);
+        for text in &["1000", "1.234", "", "-", ".", "1.2.3", "1e5", "12a"] {
+            assert_eq!(dtype.parse(text).unwrap_err().code(), StatusCode::Invalid, "{}", text);
+        }
+
+        assert_eq!(dtype.format(12345), "123.45");
+        assert_eq!(dtype.format(-5), "-0.05");
+        assert_eq!(dtype.format(0), "0.00");
+        assert_eq!(Decimal::new(3, 0).unwrap().format(-42), "-42");
+
+        let wider = Decimal::new(10, 4).unwrap();
+        assert_eq!(dtype.rescale(12345, &wider).unwrap(), 1234500);
+        assert_eq!(wider.rescale(1234500, &dtype).unwrap(), 12345);
+        assert!(wider.rescale(1234567, &dtype).is_err());
+        assert!(dtype.rescale(99999, &Decimal::new(5, 3).unwrap()).is_err());
+
+        let max = Decimal::new(38, 0).unwrap();
+        let nines = "9".repeat(38);
+        assert_eq!(max.format(max.parse(&nines).unwrap()), nines);
+        assert!(max.parse(&"9".repeat(39)).is_err());
+    }
+
+    #[test]
+    fn test_decimal_array() {
+        let mut a = DecimalArray::new(Decimal::new(10, 2).unwrap());
+        a.push_str("19.99").unwrap();
+        a.push_null().unwrap();
+        a.push(-250).unwrap();
+        assert!(a.push(10i128.pow(10)).is_err());
+        assert!(a.push_str("0.001").is_err());
+        assert!(a.extend_from_slice(&[1, -10i128.pow(10)]).is_err());
+
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.precision(), 10);
+        assert_eq!(a.scale(), 2);
+        assert_eq!(a.value(0), 1999);
+        assert_eq!(a.value_as_string(0), "19.99");
+        assert_eq!(a.value_as_string(2), "-2.50");
+
+        let b = a.rescale(Decimal::new(12, 4).unwrap()).unwrap();
+        assert_eq!(b.values(), &[199900, 0, -25000]);
+        assert!(b.is_null(1));
+        assert_eq!(b.value_as_string(0), "19.9900");
+
+        let dynamic: ArrayRef = Arc::new(b);
+        let dtype = types::DataType::Decimal { precision: 12, scale: 4 };
+        assert_eq!(dynamic.data_type(), dtype);
+        assert_eq!(dtype.bit_width(), 128);
+        assert_eq!(dtype.to_string(), "decimal(12, 4)");
+    }
+
+    #[test]
+    fn test_date_arrays() {
+        assert!(Date32::new(DateUnit::Milli).is_err());
+        assert!(Date64::new(DateUnit::Day).is_err());
+
+        let mut a = Date32Array::new(Date32::new(DateUnit::Day).unwrap());
+        a.extend_from_slice(&[0, 19_000, -1]).unwrap();
+        assert_eq!(a.value_as_date(0).to_string(), "1970-01-01");
+        assert_eq!(a.value_as_date(1).to_string(), "2022-01-08");
+        assert_eq!(a.value_as_date(2).to_string(), "1969-12-31");
+
+        let mut b = Date64Array::new(Date64::new(DateUnit::Milli).unwrap());
+        b.push(1_641_600_000_000).unwrap();
+        b.push(-1).unwrap();
+        assert_eq!(b.value_as_date(0), Date::new(2022, 1, 8).unwrap());
+        assert_eq!(b.value_as_date(1), Date::new(1969, 12, 31).unwrap());
+
+        let dynamic: ArrayRef = Arc::new(b);
+        assert_eq!(dynamic.data_type(), types::DataType::Date64(DateUnit::Milli));
+    }
+
+    #[test]
+    fn test_interval_arrays() {
+        let mut a = IntervalYearMonthArray::new(IntervalYearMonth);
+        a.extend_from_slice(&[12, -3]).unwrap();
+        let dtype = types::DataType::Interval(IntervalUnit::YearMonth);
+        assert_eq!(a.data_type(), dtype);
+        assert_eq!(dtype.bit_width(), 32);
+
+        let mut b = IntervalDayTimeArray::new(IntervalDayTime);
+        b.push((3 << 32) | 500).unwrap();
+        let dtype = types::DataType::Interval(IntervalUnit::DayTime);
+        assert_eq!(b.data_type(), dtype);
+        assert_eq!(dtype.bit_width(), 64);
+    }
+
+    #[test]
+    fn test_time_arrays() {
+        assert!(Time32::new(TimeUnit::Micro).is_err());
+        assert!(Time64::new(TimeUnit::Second).is_err());
+
+        let mut a = Time32Array::new(Time32::new(TimeUnit::Milli).unwrap());
+        a.push(45_296_789).unwrap();
+        a.push(86_400_000).unwrap();
+        assert_eq!(a.value_as_time(0).unwrap().to_string(), "12:34:56.789");
+        assert_eq!(a.value_as_time(1).unwrap_err().code(), StatusCode::Invalid);
+
+        let mut b = Time64Array::new(Time64::new(TimeUnit::Nano).unwrap());
+        b.push(1).unwrap();
+        assert_eq!(b.value_as_time(0).unwrap().to_string(), "00:00:00.000000001");
+        assert_eq!(b.dtype().data_type().bit_width(), 64);
+    }
+
+    #[test]
+    fn test_timestamp_array() {
+        let dtype = Timestamp::new(TimeUnit::Milli, Some("Europe/Budapest"));
+        let mut a = TimestampArray::new(dtype.clone());
+        a.push(1_500_000_000_123).unwrap();
+        a.push_null().unwrap();
+        a.push(-1).unwrap();
+
+        assert_eq!(a.timezone(), Some("Europe/Budapest"));
+        assert_eq!(a.dtype(), dtype);
+        assert_eq!(a.value_as_datetime(0).to_string(), "2017-07-14T02:40:00.123");
+        assert_eq!(a.value_as_datetime(2).to_string(), "1969-12-31T23:59:59.999");
+        assert!(a.is_null(1));
+
+        let dynamic: ArrayRef = Arc::new(a);
+        let expected = types::DataType::Timestamp {
+            unit: TimeUnit::Milli,
+            timezone: Some(String::from("Europe/Budapest"))
+        };
+        assert_eq!(dynamic.data_type(), expected);
+        assert_eq!(expected.bit_width(), 64);
+
+        let list = ListArray::new(List(Timestamp::new(TimeUnit::Second, None)));
+        assert_eq!(list.data.values().dtype().timezone(), None);
+    }
+
+    fn struct_children() -> (Struct, Vec<ArrayRef>) {
+        let dtype = Struct::new(vec![
+            types::Field::new("id", types::DataType::Int32, false),
+            types::Field::new("name", types::DataType::String, true)
+        ]);
+        let mut ids = Int32Array::new(Int32);
+        ids.extend_from_slice(&[1, 2, 3]).unwrap();
+        let mut names = StringArray::new(Utf8);
+        names.push("a").unwrap();
+        names.push_null().unwrap();
+        names.push("c").unwrap();
+        (dtype, vec![Arc::new(ids), Arc::new(names)])
+    }
+
+    #[test]
+    fn test_struct_array() {
+        let (dtype, children) = struct_children();
+        let mut nulls = BitMap::new();
+        for &valid in &[true, true, false] {
+            nulls.push(valid).unwrap();
+        }
+        let a = StructArray::from_children(dtype, children, Some(nulls)).unwrap();
+
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.num_columns(), 2);
+        assert_eq!(a.null_count(), 1);
+        assert!(a.is_null(2));
+        assert_eq!(a.dtype().field(1).unwrap().name(), "name");
+        assert_eq!(a.dtype().index_of("name"), Some(1));
+        assert!(a.column_by_name("missing").is_none());
+
+        let names = a.column_by_name("name").unwrap();
+        let names = names.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(names.get(0), Some("a"));
+        assert_eq!(names.get(1), None);
+        let ids = a.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(ids.values(), &[1, 2, 3]);
+
+        let dynamic: ArrayRef = Arc::new(a);
+        assert_eq!(dynamic.data_type().to_string(),
+                   "struct<id: int32 not null, name: string>");
+    }
+
+    #[test]
+    fn test_struct_array_validation() {
+        let (dtype, children) = struct_children();
+        let err = StructArray::from_children(dtype.clone(), children[..1].to_vec(), None);
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+
+        let err = StructArray::from_children(dtype.clone(),
+                                             vec![children[1].clone(), children[0].clone()], None);
+        assert_eq!(err.err().unwrap().code(), StatusCode::TypeError);
+
+        let mut short = Int32Array::new(Int32);
+        short.push(1).unwrap();
+        let err = StructArray::from_children(dtype.clone(),
+                                             vec![Arc::new(short), children[1].clone()], None);
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+
+        let mut ids = Int32Array::new(Int32);
+        ids.push_option(None).unwrap();
+        ids.extend_from_slice(&[2, 3]).unwrap();
+        let err = StructArray::from_children(dtype.clone(),
+                                             vec![Arc::new(ids), children[1].clone()], None);
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+
+        let err = StructArray::from_children(dtype, children, Some(BitMap::new()));
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+    }
+
+    fn number_or_string(mode: UnionMode) -> Union {
+        Union::new(mode, vec![0, 5], vec![
+            types::Field::new("number", types::DataType::Double, true),
+            types::Field::new("string", types::DataType::String, true)
+        ]).unwrap()
+    }
+
+    #[test]
+    fn test_union_type() {
+        let fields = vec![types::Field::new("a", types::DataType::Int8, true)];
+        assert!(Union::new(UnionMode::Sparse, vec![0, 1], fields.clone()).is_err());
+        assert!(Union::new(UnionMode::Sparse, vec![-1], fields.clone()).is_err());
+        let fields = vec![fields[0].clone(), fields[0].clone()];
+        assert!(Union::new(UnionMode::Dense, vec![3, 3], fields).is_err());
+
+        let dtype = number_or_string(UnionMode::Dense);
+        assert_eq!(dtype.child_index(5), Some(1));
+        assert_eq!(dtype.child_index(1), None);
+        assert_eq!(dtype.data_type().to_string(),
+                   "union[dense]<number: double=0, string: string=5>");
+    }
+
+    #[test]
+    fn test_sparse_union_array() {
+        let mut numbers = Float64Array::new(Float64);
+        numbers.extend_from_slice(&[1.5, 0.0, 3.0]).unwrap();
+        let mut strings = StringArray::new(Utf8);
+        strings.push("").unwrap();
+        strings.push("two").unwrap();
+        strings.push("").unwrap();
+
+        let a = UnionArray::from_parts(number_or_string(UnionMode::Sparse), &[0, 5, 0], None,
+                                       vec![Arc::new(numbers), Arc::new(strings)]).unwrap();
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.mode(), UnionMode::Sparse);
+        assert_eq!(a.type_ids(), &[0, 5, 0]);
+        assert!(a.value_offsets().is_empty());
+
+        let (child, offset) = a.value(1);
+        let strings = child.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(strings.value(offset), "two");
+        let (child, offset) = a.value(2);
+        let numbers = child.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(numbers.value(offset), 3.0);
+        assert_eq!(a.child(0).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_dense_union_array() {
+        let mut numbers = Float64Array::new(Float64);
+        numbers.extend_from_slice(&[1.5, 3.0]).unwrap();
+        let mut strings = StringArray::new(Utf8);
+        strings.push("two").unwrap();
+
+        let children: Vec<ArrayRef> = vec![Arc::new(numbers), Arc::new(strings)];
+        let dtype = number_or_string(UnionMode::Dense);
+        let a = UnionArray::from_parts(dtype.clone(), &[0, 5, 0], Some(&[0, 0, 1]),
+                                       children.clone()).unwrap();
+        assert_eq!(a.value_offsets(), &[0, 0, 1]);
+        assert_eq!(a.type_id(1), 5);
+        let (child, offset) = a.value(2);
+        assert_eq!(offset, 1);
+        assert_eq!(child.as_any().downcast_ref::<Float64Array>().unwrap().value(offset), 3.0);
+
+        let dynamic: ArrayRef = Arc::new(a);
+        assert_eq!(dynamic.data_type(), dtype.data_type());
+
+        let err = UnionArray::from_parts(dtype.clone(), &[0], None, children.clone());
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+        let err = UnionArray::from_parts(dtype.clone(), &[0, 5], Some(&[0, 1]), children.clone());
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+        let err = UnionArray::from_parts(dtype.clone(), &[1], Some(&[0]), children.clone());
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+        // out of order offsets into the numbers
+        let err = UnionArray::from_parts(dtype, &[0, 5, 0], Some(&[1, 0, 0]), children);
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+    }
+
+    #[test]
+    fn test_dictionary_builder() {
+        let mut builder = DictionaryBuilder::new(Dictionary(Int8, Utf8));
+        for value in &["red", "green", "red", "blue", "green", "red"] {
+            builder.push(*value).unwrap();
+        }
+        builder.push_null().unwrap();
+        assert_eq!(builder.len(), 7);
+        assert_eq!(builder.num_values(), 3);
+
+        let a = builder.finish();
+        assert_eq!(a.keys(), &[0, 1, 0, 2, 1, 0, 0]);
+        assert_eq!(a.values().len(), 3);
+        assert_eq!(a.null_count(), 1);
+        assert_eq!(a.value_index(6), None);
+        // only the dictionary tracks validity
+        assert!(a.data.keys.nulls().is_none());
+        let decoded: Vec<_> = (0..a.len())
+            .map(|i| a.value_index(i).map(|v| a.values().value(v)))
+            .collect();
+        assert_eq!(decoded, vec![Some("red"), Some("green"), Some("red"), Some("blue"),
+                                 Some("green"), Some("red"), None]);
+
+        let dynamic: ArrayRef = Arc::new(a);
+        let dtype = dynamic.data_type();
+        assert_eq!(dtype.to_string(), "dictionary<values=string, indices=int8>");
+        assert_eq!(dtype.bit_width(), 8);
+    }
+
+    #[test]
+    fn test_dictionary_builder_overflow() {
+        let mut builder = DictionaryBuilder::new(Dictionary(UInt8, UInt16));
+        for i in 0..256 {
+            builder.push(&i).unwrap();
+        }
+        assert_eq!(builder.push(&255).unwrap(), 255);
+        assert_eq!(builder.push(&256).unwrap_err().code(), StatusCode::Invalid);
+        assert_eq!(builder.len(), 257);
+    }
+
+    #[test]
+    fn test_dictionary_shared_values() {
+        let mut values = Float64Array::new(Float64);
+        values.extend_from_slice(&[0.5, 1.5]).unwrap();
+        let values = Arc::new(values);
+
+        let dtype = Dictionary(UInt32, Float64);
+        let mut a = DictionaryArray::with_values(dtype, values.clone()).unwrap();
+        let mut b = DictionaryArray::with_values(dtype, values.clone()).unwrap();
+        a.push_key(1).unwrap();
+        a.push_null().unwrap();
+        b.push_key(0).unwrap();
+        assert_eq!(a.push_key(2).unwrap_err().code(), StatusCode::Invalid);
+
+        assert!(Arc::ptr_eq(a.values(), b.values()));
+        assert_eq!(a.keys(), &[1, 0]);
+        assert_eq!(a.value_index(0), Some(1));
+        assert!(a.is_null(1));
+        assert_eq!(b.value_index(0), Some(0));
+
+        let values = Arc::new(DecimalArray::new(Decimal::new(10, 2).unwrap()));
+        let dtype = Dictionary(Int8, Decimal::new(12, 2).unwrap());
+        let err = DictionaryArray::with_values(dtype, values);
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+    }
+
+    fn tags() -> Map {
+        Map::new(types::Field::new("key", types::DataType::String, false),
+                 types::Field::new("value", types::DataType::Int64, true), false).unwrap()
+    }
+
+    #[test]
+    fn test_map_array() {
+        let mut keys = StringArray::new(Utf8);
+        let mut items = Int64Array::new(Int64);
+        for &(key, item) in &[("a", Some(1)), ("b", None), ("c", Some(3))] {
+            keys.push(key).unwrap();
+            items.push_option(item).unwrap();
+        }
+        let mut nulls = BitMap::new();
+        for &valid in &[true, true, false, true] {
+            nulls.push(valid).unwrap();
+        }
+        let a = MapArray::from_parts(tags(), &[0, 2, 2, 2, 3], Arc::new(keys), Arc::new(items),
+                                     Some(nulls)).unwrap();
+
+        assert_eq!(a.len(), 4);
+        assert_eq!(a.null_count(), 1);
+        assert!(a.is_null(2));
+        assert_eq!(a.value_range(0), 0..2);
+        assert_eq!(a.entries().len(), 3);
+
+        let row: Vec<_> = a.iter_row::<StringArray, Int64Array>(0).unwrap().collect();
+        assert_eq!(row, vec![("a", Some(1)), ("b", None)]);
+        assert_eq!(a.iter_row::<StringArray, Int64Array>(1).unwrap().count(), 0);
+        let row: Vec<_> = a.iter_row::<StringArray, Int64Array>(3).unwrap().collect();
+        assert_eq!(row, vec![("c", Some(3))]);
+        assert_eq!(a.iter_row::<StringArray, Int32Array>(0).err().unwrap().code(),
+                   StatusCode::TypeError);
+
+        let dynamic: ArrayRef = Arc::new(a);
+        assert_eq!(dynamic.data_type().to_string(),
+                   "map<key: string not null, value: int64>");
+    }
+
+    #[test]
+    fn test_map_array_validation() {
+        let key = types::Field::new("key", types::DataType::String, true);
+        let value = types::Field::new("value", types::DataType::Int64, true);
+        assert_eq!(Map::new(key, value, true).unwrap_err().code(), StatusCode::Invalid);
+
+        let mut keys = StringArray::new(Utf8);
+        keys.push("a").unwrap();
+        keys.push_null().unwrap();
+        let mut items = Int64Array::new(Int64);
+        items.extend_from_slice(&[1, 2]).unwrap();
+        let keys: ArrayRef = Arc::new(keys);
+        let items: ArrayRef = Arc::new(items);
+
+        let err = MapArray::from_parts(tags(), &[0, 2], keys.clone(), items.clone(), None);
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+
+        let mut valid_keys = StringArray::new(Utf8);
+        valid_keys.push("a").unwrap();
+        valid_keys.push("b").unwrap();
+        let valid_keys: ArrayRef = Arc::new(valid_keys);
+        let err = MapArray::from_parts(tags(), &[0, 3], valid_keys.clone(), items.clone(), None);
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+        let err = MapArray::from_parts(tags(), &[0, 2], items, valid_keys, None);
+        assert_eq!(err.err().unwrap().code(), StatusCode::TypeError);
+    }
+
+    #[test]
+    fn test_null_array() {
+        let mut a = NullArray::new(3);
+        a.push_null();
+        a.append_nulls(2);
+        assert_eq!(a.len(), 6);
+        assert_eq!(a.slice(1, 4).len(), 4);
+        assert_eq!(mem::size_of::<NullArray>(), mem::size_of::<usize>());
+
+        let dynamic: ArrayRef = Arc::new(a);
+        assert_eq!(dynamic.null_count(), 6);
+        assert!(dynamic.is_null(5));
+        assert!(!dynamic.is_valid(0));
+        assert_eq!(dynamic.data_type(), types::DataType::NA);
+        assert_eq!(dynamic.data_type().bit_width(), 0);
+
+        let schema = types::Schema::new(vec![types::Field::new("empty", types::DataType::NA, true)]);
+        assert!(schema.validate(&[dynamic]).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_null_array_out_of_bounds() {
+        NullArray::new(2).is_null(2);
+    }
+
+    #[test]
+    fn test_float16_array() {
+        let mut a = Float16Array::new(HalfFloat);
+        a.push(f16::from_f32(0.5)).unwrap();
+        a.push_null().unwrap();
+        a.extend_from_slice(&[f16::INFINITY, f16::from_f64(-2.0)]).unwrap();
+
+        assert_eq!(a.len(), 4);
+        assert_eq!(a.get(1), None);
+        assert_eq!(a.value(1).to_bits(), 0);
+        assert_eq!(a.value(0).to_f32(), 0.5);
+        assert!(a.value(2).is_infinite());
+        assert_eq!(a.dtype().precision(), Precision::Half);
+        assert_eq!(a.dtype().bits(), 16);
+        let sum = a.values().iter().filter(|v| v.is_finite()).fold(f16::ZERO, |acc, &v| acc + v);
+        assert_eq!(sum.to_f32(), -1.5);
+
+        let dynamic: ArrayRef = Arc::new(a);
+        assert_eq!(dynamic.data_type(), types::DataType::HalfFloat);
+        assert_eq!(dynamic.data_type().bit_width(), 16);
+    }
+
+    #[test]
+    fn test_large_string_and_binary_arrays() {
+        let mut a = LargeStringArray::new(LargeUtf8);
+        a.push("large").unwrap();
+        a.push_null().unwrap();
+        a.push("ö").unwrap();
+        assert_eq!(a.value_offsets(), &[0i64, 5, 5, 7]);
+        assert_eq!(a.value(2), "ö");
+        assert_eq!(a.get(1), None);
+        assert_eq!(a.value_length(2), 2);
+
+        let b = LargeStringArray::from_parts(&[0, 1, 3], b"abc").unwrap();
+        assert_eq!(b.value(1), "bc");
+        let err = LargeStringArray::from_parts(&[0, -1], b"abc");
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+
+        let mut c = LargeBinaryArray::new(LargeBinary);
+        c.push(&[1, 2]).unwrap();
+        c.push_option(None).unwrap();
+        assert_eq!(c.value(0), &[1, 2]);
+        assert_eq!(c.value_range(1), 2..2);
+
+        let dynamic: ArrayRef = Arc::new(a);
+        assert_eq!(dynamic.data_type(), types::DataType::LargeString);
+        let dynamic: ArrayRef = Arc::new(c);
+        assert_eq!(dynamic.data_type(), types::DataType::LargeBinary);
+    }
+
+    #[test]
+    fn test_list_array() {
+        let mut a = ListArray::new(List(Int32));
+        a.values_mut().extend_from_slice(&[1, 2, 3]).unwrap();
+        a.append(true).unwrap();
+        a.push_null().unwrap();
+        a.append(true).unwrap();
+        a.values_mut().push(4).unwrap();
+        a.append(true).unwrap();
+
+        assert_eq!(a.len(), 4);
+        assert_eq!(a.null_count(), 1);
+        assert_eq!(a.value_offsets(), &[0, 3, 3, 3, 4]);
+        assert_eq!(a.value_range(0), 0..3);
+        assert_eq!(a.value_length(2), 0);
+        assert_eq!(&a.values().values()[a.value_range(3)], &[4]);
+    }
+
+    #[test]
+    fn test_large_list_array() {
+        let mut a = LargeListArray::new(LargeList(Utf8));
+        a.values_mut().push("x").unwrap();
+        a.values_mut().push("y").unwrap();
+        a.append(true).unwrap();
+        a.push_null().unwrap();
+
+        assert_eq!(a.value_offsets(), &[0i64, 2, 2]);
+        assert_eq!(a.values().value(1), "y");
+        assert!(a.is_null(1));
+
+        let dynamic: ArrayRef = Arc::new(a);
+        let dtype = dynamic.data_type();
+        assert_eq!(dtype, types::DataType::LargeList(Box::new(types::DataType::String)));
+        assert_eq!(dtype.to_string(), "large_list<item: string>");
+    }
+
+    #[test]
+    fn test_fixed_size_list_array() {
+        let mut a = FixedSizeListArray::new(FixedSizeList::new(Float32, 3).unwrap());
+        a.push(&[1.0, 2.0, 3.0]).unwrap();
+        a.push_null().unwrap();
+        a.push_option(Some(&[4.0, 5.0, 6.0])).unwrap();
+        assert_eq!(a.push(&[1.0, 2.0]).unwrap_err().code(), StatusCode::Invalid);
+
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.list_size(), 3);
+        assert_eq!(a.null_count(), 1);
+        assert_eq!(a.value(0), &[1.0, 2.0, 3.0]);
+        assert_eq!(a.get(1), None);
+        assert_eq!(a.value(1), &[0.0, 0.0, 0.0]);
+        assert_eq!(a.value_range(2), 6..9);
+
+        let dynamic: ArrayRef = Arc::new(a);
+        let dtype = dynamic.data_type();
+        assert_eq!(dtype, types::DataType::FixedSizeList(Box::new(types::DataType::Float), 3));
+        assert_eq!(dtype.to_string(), "fixed_size_list<item: float>[3]");
+    }
+
+    #[test]
+    fn test_fixed_size_list_flat_values() {
+        let mut values = Int16Array::new(Int16);
+        values.extend_from_slice(&[1, 2, 3, 4, 5, 6]).unwrap();
+        let pairs = FixedSizeList::new(Int16, 2).unwrap();
+        let a = FixedSizeListArray::from_values(pairs, values, None).unwrap();
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.value(2), &[5, 6]);
+        let values = a.into_values();
+        assert_eq!(values.values(), &[1, 2, 3, 4, 5, 6]);
+
+        let quads = FixedSizeList::new(Int16, 4).unwrap();
+        let err = FixedSizeListArray::from_values(quads, values, None);
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+
+        let mut b = FixedSizeListArray::new(FixedSizeList::new(Utf8, 2).unwrap());
+        b.values_mut().push("x").unwrap();
+        assert_eq!(b.append(true).unwrap_err().code(), StatusCode::Invalid);
+        b.values_mut().push("y").unwrap();
+        b.append(true).unwrap();
+        assert_eq!(b.values().value(b.value_range(0).end - 1), "y");
+    }
+
+    #[test]
+    fn test_fixed_size_list_of_size_zero() {
+        assert_eq!(FixedSizeList::new(Int16, -1).unwrap_err().code(), StatusCode::Invalid);
+
+        let empty = FixedSizeList::new(Int16, 0).unwrap();
+        let err = FixedSizeListArray::from_values(empty, Int16Array::new(Int16), None);
+        assert_eq!(err.err().unwrap().code(), StatusCode::Invalid);
+
+        let mut nulls = BitMap::new();
+        for &valid in &[true, false, true] {
+            nulls.push(valid).unwrap();
+        }
+        let a = FixedSizeListArray::from_values(empty, Int16Array::new(Int16), Some(nulls)).unwrap();
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.null_count(), 1);
+        assert!(a.value(0).is_empty());
+    }
+
+    #[test]
+    fn test_dyn_array() {
+        let mut ints = Int32Array::new(Int32);
+        ints.push(1).unwrap();
+        ints.push_null().unwrap();
+        let mut floats = Float64Array::new(Float64);
+        floats.push(1.0).unwrap();
+
+        let columns: Vec<ArrayRef> = vec![Arc::new(ints), Arc::new(floats),
+                                          Arc::new(ListArray::new(List(Int16)))];
+        assert_eq!(columns[0].len(), 2);
+        assert_eq!(columns[0].null_count(), 1);
+        assert!(columns[0].is_null(1));
+        assert!(columns[1].is_valid(0));
+        assert!(columns[2].is_empty());
+
+        assert_eq!(columns[0].data_type(), types::DataType::Int32);
+        assert_eq!(columns[1].data_type(), types::DataType::Double);
+        assert_eq!(columns[2].data_type(),
+                   types::DataType::List(Box::new(types::DataType::Int16)));
+
+        let ints = columns[0].as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(ints.get(0), Some(1));
+        assert!(columns[0].as_any().downcast_ref::<Int64Array>().is_none());
+    }
+
+    #[test]
+    fn test_push_with_checked_pool() {
+        let pool = Arc::new(CheckedMemoryPool::new(Arc::new(DefaultMemoryPool::new())));
+        {
+            let mut a = TypedArray::with_pool(Int16, pool.clone());
+            for i in 0..1000 {
+                a.push(i).unwrap();
+            }
+            pool.verify();
+        }
+        assert!(pool.leaks().is_empty());
+    }
+
+    #[test]
+    fn test_push_exceeding_pool_limit() {
+        let pool = Arc::new(DefaultMemoryPool::with_limit(64));
+        let mut a = TypedArray::with_pool(Int64, pool.clone());
+        for i in 0..8 {
+            a.push(i).unwrap();
         }
+        let err = a.push(8).unwrap_err();
+        assert_eq!(err.code(), StatusCode::OutOfMemory);
+        assert_eq!(a.len(), 8);
+        assert_eq!(pool.bytes_allocated(), 64);
     }
 
 }
@@ -0,0 +1,624 @@
+use std::ptr;
+
+use buffer::MutableBuffer;
+use errors::{ArrowError, StatusCode, Result};
+use mempool::{MemoryPoolRef, default_pool};
+
+
+#[inline]
+pub fn bytes_for(bits: usize) -> usize {
+    bits.div_ceil(8)
+}
+
+
+// little endian load of 8 bytes, missing bytes past the end are zeros
+#[inline]
+fn load_word(bytes: &[u8], offset: usize) -> u64 {
+    if offset + 8 <= bytes.len() {
+        unsafe {
+            u64::from_le(ptr::read_unaligned(bytes.as_ptr().add(offset) as *const u64))
+        }
+    } else {
+        let mut word = 0u64;
+        for (i, byte) in bytes.iter().skip(offset).enumerate() {
+            word |= (*byte as u64) << (i * 8);
+        }
+        word
+    }
+}
+
+
+// the 64 bits starting at an arbitrary bit offset, bits past the end are zeros
+#[inline]
+pub fn word_at(bytes: &[u8], bit: usize) -> u64 {
+    let byte = bit / 8;
+    let shift = bit % 8;
+    let lo = load_word(bytes, byte);
+    if shift == 0 {
+        lo
+    } else {
+        let hi = bytes.get(byte + 8).cloned().unwrap_or(0) as u64;
+        (lo >> shift) | (hi << (64 - shift))
+    }
+}
+
+
+// validity bitmap, one bit per slot, least significant bit first
+#[derive(Debug)]
+pub struct BitMap {
+    data: MutableBuffer,
+    len: usize
+}
+
+
+impl BitMap {
+
+    pub fn new() -> Self {
+        Self::with_pool(default_pool())
+    }
+
+    pub fn with_pool(pool: MemoryPoolRef) -> Self {
+        BitMap {
+            data: MutableBuffer::with_pool(pool),
+            len: 0
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.data.as_slice()
+    }
+
+    pub fn as_bit_slice(&self) -> BitSlice<'_> {
+        BitSlice::new(self.data.as_slice(), 0, self.len)
+    }
+
+    // bits [offset, offset + len) without copying
+    pub fn slice(&self, offset: usize, len: usize) -> BitSlice<'_> {
+        assert!(offset + len <= self.len,
+                "slice [{}, {}) is out of bounds of bitmap with length {}",
+                offset, offset + len, self.len);
+        BitSlice::new(self.data.as_slice(), offset, len)
+    }
+
+    // packs 64 bits per word, bits past len are cleared
+    fn from_words<I>(words: I, len: usize, pool: MemoryPoolRef) -> Result<BitMap>
+        where I: Iterator<Item = u64>
+    {
+        let mut data = MutableBuffer::with_capacity(bytes_for(len), pool)?;
+        for word in words {
+            data.push(word.to_le())?;
+        }
+        data.resize(bytes_for(len))?;
+        if !len.is_multiple_of(8) {
+            let last = data.len() - 1;
+            data.as_mut_slice()[last] &= (1u8 << (len % 8)) - 1;
+        }
+        Ok(BitMap { data, len })
+    }
+
+    #[inline]
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "index {} out of bounds of bitmap with length {}", i, self.len);
+        self.data.as_slice()[i >> 3] & (1 << (i & 7)) != 0
+    }
+
+    #[inline]
+    pub fn set(&mut self, i: usize) {
+        assert!(i < self.len, "index {} out of bounds of bitmap with length {}", i, self.len);
+        self.data.as_mut_slice()[i >> 3] |= 1 << (i & 7);
+    }
+
+    #[inline]
+    pub fn unset(&mut self, i: usize) {
+        assert!(i < self.len, "index {} out of bounds of bitmap with length {}", i, self.len);
+        self.data.as_mut_slice()[i >> 3] &= !(1 << (i & 7));
+    }
+
+    pub fn push(&mut self, value: bool) -> Result<()> {
+        if self.len.is_multiple_of(8) {
+            self.data.push(0u8)?;
+        }
+        self.len += 1;
+        if value {
+            self.set(self.len - 1);
+        }
+        Ok(())
+    }
+
+    // makes room for additional more bits
+    pub fn reserve(&mut self, additional: usize) -> Result<()> {
+        let bytes = bytes_for(self.len + additional);
+        self.data.reserve(bytes.saturating_sub(self.data.len()))
+    }
+
+    pub fn append_n(&mut self, n: usize, value: bool) -> Result<()> {
+        let start = self.len;
+        self.data.resize(bytes_for(start + n))?;
+        self.len += n;
+        if value {
+            // the partial byte first, then whole bytes at once
+            let mut i = start;
+            while i < self.len && !i.is_multiple_of(8) {
+                self.set(i);
+                i += 1;
+            }
+            let full = (self.len - i) / 8;
+            for byte in &mut self.data.as_mut_slice()[i / 8..i / 8 + full] {
+                *byte = 0xff;
+            }
+            for j in i + full * 8..self.len {
+                self.set(j);
+            }
+        }
+        Ok(())
+    }
+
+    // the 64 bits starting at bit i, bits past len are zeros
+    #[inline]
+    fn word(&self, i: usize) -> u64 {
+        let word = word_at(self.as_slice(), i);
+        let remaining = self.len - i;
+        if remaining < 64 {
+            word & ((1 << remaining) - 1)
+        } else {
+            word
+        }
+    }
+
+    pub fn count_set_bits(&self) -> usize {
+        self.as_bit_slice().count_set_bits()
+    }
+
+    // index of the first bit at or after i equal to value, len if there is none
+    fn find(&self, mut i: usize, value: bool) -> usize {
+        while i < self.len {
+            let mut word = if value { self.word(i) } else { !self.word(i) };
+            let remaining = self.len - i;
+            if remaining < 64 {
+                word &= (1 << remaining) - 1;
+            }
+            if word != 0 {
+                return i + word.trailing_zeros() as usize;
+            }
+            i += 64;
+        }
+        self.len
+    }
+
+    pub fn iter_set_bits<'a>(&'a self) -> SetBits<'a> {
+        SetBits {
+            bitmap: self,
+            base: 0,
+            word: if self.len > 0 { self.word(0) } else { 0 }
+        }
+    }
+
+    // (start, length) pairs of consecutive set bits
+    pub fn iter_runs<'a>(&'a self) -> Runs<'a> {
+        Runs {
+            bitmap: self,
+            pos: 0
+        }
+    }
+
+}
+
+
+impl Default for BitMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+pub struct SetBits<'a> {
+    bitmap: &'a BitMap,
+    base: usize,
+    word: u64
+}
+
+
+impl<'a> Iterator for SetBits<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        loop {
+            if self.word != 0 {
+                let bit = self.word.trailing_zeros() as usize;
+                self.word &= self.word - 1;
+                return Some(self.base + bit);
+            }
+            self.base += 64;
+            if self.base >= self.bitmap.len {
+                return None;
+            }
+            self.word = self.bitmap.word(self.base);
+        }
+    }
+}
+
+
+pub struct Runs<'a> {
+    bitmap: &'a BitMap,
+    pos: usize
+}
+
+
+impl<'a> Iterator for Runs<'a> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let start = self.bitmap.find(self.pos, true);
+        if start >= self.bitmap.len {
+            self.pos = start;
+            return None;
+        }
+        let end = self.bitmap.find(start, false);
+        self.pos = end;
+        Some((start, end - start))
+    }
+}
+
+
+// borrowed range of bits starting at an arbitrary, not necessarily byte
+// aligned offset, e.g. the validity of a sliced array
+#[derive(Copy, Clone, Debug)]
+pub struct BitSlice<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    len: usize
+}
+
+
+impl<'a> BitSlice<'a> {
+
+    pub fn new(bytes: &'a [u8], offset: usize, len: usize) -> Self {
+        assert!(bytes_for(offset + len) <= bytes.len(),
+                "{} bits at offset {} don't fit into {} bytes", len, offset, bytes.len());
+        BitSlice { bytes, offset, len }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    #[inline]
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "index {} out of bounds of bitmap with length {}", i, self.len);
+        let bit = self.offset + i;
+        self.bytes[bit >> 3] & (1 << (bit & 7)) != 0
+    }
+
+    // the 64 bits starting at bit i, bits past len are unspecified
+    #[inline]
+    fn word(&self, i: usize) -> u64 {
+        word_at(self.bytes, self.offset + i)
+    }
+
+    fn words(&self) -> impl Iterator<Item = u64> + 'a {
+        let slice = *self;
+        (0..self.len).step_by(64).map(move |i| slice.word(i))
+    }
+
+    pub fn count_set_bits(&self) -> usize {
+        let mut count = 0;
+        let mut i = 0;
+        while i < self.len {
+            let mut word = self.word(i);
+            let remaining = self.len - i;
+            if remaining < 64 {
+                word &= (1 << remaining) - 1;
+            }
+            count += word.count_ones() as usize;
+            i += 64;
+        }
+        count
+    }
+
+}
+
+
+pub fn unary<F>(input: BitSlice, pool: MemoryPoolRef, op: F) -> Result<BitMap>
+    where F: Fn(u64) -> u64
+{
+    BitMap::from_words(input.words().map(op), input.len(), pool)
+}
+
+
+pub fn binary<F>(left: BitSlice, right: BitSlice, pool: MemoryPoolRef, op: F) -> Result<BitMap>
+    where F: Fn(u64, u64) -> u64
+{
+    if left.len() != right.len() {
+        return Err(ArrowError::new(StatusCode::Invalid, format!(
+            "bitmaps must have the same length, got {} and {}", left.len(), right.len())));
+    }
+    let words = left.words().zip(right.words()).map(|(l, r)| op(l, r));
+    BitMap::from_words(words, left.len(), pool)
+}
+
+
+pub fn and(left: BitSlice, right: BitSlice) -> Result<BitMap> {
+    and_with_pool(left, right, default_pool())
+}
+
+
+pub fn and_with_pool(left: BitSlice, right: BitSlice, pool: MemoryPoolRef) -> Result<BitMap> {
+    binary(left, right, pool, |l, r| l & r)
+}
+
+
+pub fn or(left: BitSlice, right: BitSlice) -> Result<BitMap> {
+    or_with_pool(left, right, default_pool())
+}
+
+
+pub fn or_with_pool(left: BitSlice, right: BitSlice, pool: MemoryPoolRef) -> Result<BitMap> {
+    binary(left, right, pool, |l, r| l | r)
+}
+
+
+pub fn xor(left: BitSlice, right: BitSlice) -> Result<BitMap> {
+    xor_with_pool(left, right, default_pool())
+}
+
+
+pub fn xor_with_pool(left: BitSlice, right: BitSlice, pool: MemoryPoolRef) -> Result<BitMap> {
+    binary(left, right, pool, |l, r| l ^ r)
+}
+
+
+pub fn and_not(left: BitSlice, right: BitSlice) -> Result<BitMap> {
+    and_not_with_pool(left, right, default_pool())
+}
+
+
+pub fn and_not_with_pool(left: BitSlice, right: BitSlice, pool: MemoryPoolRef) -> Result<BitMap> {
+    binary(left, right, pool, |l, r| l & !r)
+}
+
+
+pub fn not(input: BitSlice) -> Result<BitMap> {
+    not_with_pool(input, default_pool())
+}
+
+
+pub fn not_with_pool(input: BitSlice, pool: MemoryPoolRef) -> Result<BitMap> {
+    unary(input, pool, |w| !w)
+}
+
+
+// popcount of left & right without materializing it, callers guarantee
+// equal lengths, as for the values and validity of a single array
+pub(crate) fn count_set_bits_and(left: BitSlice, right: BitSlice) -> usize {
+    debug_assert_eq!(left.len(), right.len());
+    let mut count = 0;
+    let mut i = 0;
+    while i < left.len() {
+        let mut word = left.word(i) & right.word(i);
+        let remaining = left.len() - i;
+        if remaining < 64 {
+            word &= (1 << remaining) - 1;
+        }
+        count += word.count_ones() as usize;
+        i += 64;
+    }
+    count
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use mempool::{MemoryPool, DefaultMemoryPool};
+
+    fn from_bools(values: &[bool]) -> BitMap {
+        let mut bitmap = BitMap::new();
+        for v in values {
+            bitmap.push(*v).unwrap();
+        }
+        bitmap
+    }
+
+    #[test]
+    fn test_bitmap_push_get() {
+        let bitmap = from_bools(&[true, false, true, true, false, false, false, false, true]);
+        assert_eq!(bitmap.len(), 9);
+        assert_eq!(bitmap.as_slice(), &[0b0000_1101, 0b0000_0001]);
+        assert!(bitmap.get(0));
+        assert!(!bitmap.get(1));
+        assert!(bitmap.get(8));
+    }
+
+    #[test]
+    fn test_bitmap_append_n() {
+        let mut bitmap = from_bools(&[false, true, false]);
+        bitmap.append_n(30, true).unwrap();
+        bitmap.append_n(2, false).unwrap();
+        bitmap.append_n(0, true).unwrap();
+        bitmap.append_n(4, true).unwrap();
+
+        let mut expected = vec![false, true, false];
+        expected.extend(vec![true; 30]);
+        expected.extend(vec![false; 2]);
+        expected.extend(vec![true; 4]);
+        assert_eq!(bools(&bitmap), expected);
+        assert_eq!(bitmap.count_set_bits(), 35);
+    }
+
+    #[test]
+    fn test_bitmap_set_unset() {
+        let mut bitmap = from_bools(&[false; 20]);
+        bitmap.set(3);
+        bitmap.set(17);
+        assert!(bitmap.get(3));
+        assert!(bitmap.get(17));
+        assert_eq!(bitmap.count_set_bits(), 2);
+
+        bitmap.unset(3);
+        assert!(!bitmap.get(3));
+        assert_eq!(bitmap.count_set_bits(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bitmap_get_out_of_bounds() {
+        let bitmap = from_bools(&[true; 3]);
+        bitmap.get(3);
+    }
+
+    #[test]
+    fn test_bitmap_count_set_bits() {
+        let values: Vec<bool> = (0..200).map(|i| i % 3 == 0).collect();
+        let bitmap = from_bools(&values);
+        assert_eq!(bitmap.count_set_bits(), values.iter().filter(|v| **v).count());
+
+        assert_eq!(from_bools(&[]).count_set_bits(), 0);
+        assert_eq!(from_bools(&[true; 64]).count_set_bits(), 64);
+        assert_eq!(from_bools(&[true; 65]).count_set_bits(), 65);
+    }
+
+    #[test]
+    fn test_bitmap_iter_set_bits() {
+        let values: Vec<bool> = (0..150).map(|i| i % 7 == 0 || i == 149).collect();
+        let bitmap = from_bools(&values);
+        let expected: Vec<usize> = (0..150).filter(|i| values[*i]).collect();
+        assert_eq!(bitmap.iter_set_bits().collect::<Vec<_>>(), expected);
+        assert_eq!(from_bools(&[]).iter_set_bits().count(), 0);
+    }
+
+    #[test]
+    fn test_bitmap_iter_runs() {
+        let mut values = vec![true; 3];
+        values.extend(vec![false; 2]);
+        values.extend(vec![true; 100]);
+        values.push(false);
+        values.push(true);
+        let bitmap = from_bools(&values);
+        assert_eq!(bitmap.iter_runs().collect::<Vec<_>>(),
+                   vec![(0, 3), (5, 100), (106, 1)]);
+
+        assert_eq!(from_bools(&[false; 70]).iter_runs().count(), 0);
+        assert_eq!(from_bools(&[true; 70]).iter_runs().collect::<Vec<_>>(), vec![(0, 70)]);
+    }
+
+    #[test]
+    fn test_count_set_bits_and() {
+        let left: Vec<bool> = (0..200).map(|i| i % 3 == 0).collect();
+        let right: Vec<bool> = (0..200).map(|i| i % 2 == 0).collect();
+        let (a, b) = (from_bools(&left), from_bools(&right));
+        for &(offset, len) in &[(0, 200), (3, 150), (64, 70), (7, 0)] {
+            let expected = (offset..offset + len).filter(|&i| left[i] && right[i]).count();
+            assert_eq!(count_set_bits_and(a.slice(offset, len), b.slice(offset, len)), expected);
+        }
+    }
+
+    fn bools(bitmap: &BitMap) -> Vec<bool> {
+        (0..bitmap.len()).map(|i| bitmap.get(i)).collect()
+    }
+
+    fn check_binary<F, G>(kernel: F, expected: G)
+        where F: Fn(BitSlice, BitSlice) -> Result<BitMap>,
+              G: Fn(bool, bool) -> bool
+    {
+        let left: Vec<bool> = (0..300).map(|i| i % 3 == 0 || i % 7 == 1).collect();
+        let right: Vec<bool> = (0..300).map(|i| i % 2 == 0 || i % 11 == 4).collect();
+        let (lb, rb) = (from_bools(&left), from_bools(&right));
+
+        for &(lo, ro, len) in &[(0, 0, 300), (3, 0, 200), (0, 5, 200), (1, 70, 130),
+                                (64, 9, 191), (13, 13, 0), (7, 2, 63), (100, 37, 65)] {
+            let result = kernel(lb.slice(lo, len), rb.slice(ro, len)).unwrap();
+            let expect: Vec<bool> = (0..len).map(|i| expected(left[lo + i], right[ro + i])).collect();
+            assert_eq!(bools(&result), expect, "offsets {} {} len {}", lo, ro, len);
+            assert_eq!(result.count_set_bits(), expect.iter().filter(|v| **v).count());
+        }
+    }
+
+    #[test]
+    fn test_bitmap_and() {
+        check_binary(and, |l, r| l && r);
+    }
+
+    #[test]
+    fn test_bitmap_or() {
+        check_binary(or, |l, r| l || r);
+    }
+
+    #[test]
+    fn test_bitmap_xor() {
+        check_binary(xor, |l, r| l != r);
+    }
+
+    #[test]
+    fn test_bitmap_and_not() {
+        check_binary(and_not, |l, r| l && !r);
+    }
+
+    #[test]
+    fn test_bitmap_not() {
+        let values: Vec<bool> = (0..150).map(|i| i % 5 == 0).collect();
+        let bitmap = from_bools(&values);
+        let result = not(bitmap.slice(3, 140)).unwrap();
+        let expected: Vec<bool> = values[3..143].iter().map(|v| !v).collect();
+        assert_eq!(bools(&result), expected);
+        // bits past the length stay cleared
+        assert_eq!(result.as_slice()[17], 0b0000_1101);
+    }
+
+    #[test]
+    fn test_bitmap_kernels_with_pool() {
+        let pool = Arc::new(DefaultMemoryPool::new());
+        let bitmap = from_bools(&[true; 100]);
+        {
+            let result = and_with_pool(bitmap.slice(0, 100), bitmap.slice(0, 100),
+                                       pool.clone()).unwrap();
+            assert_eq!(result.count_set_bits(), 100);
+            let result = not_with_pool(bitmap.slice(0, 100), pool.clone()).unwrap();
+            assert_eq!(result.count_set_bits(), 0);
+            assert_eq!(pool.bytes_allocated(), 128);
+        }
+        assert_eq!(pool.bytes_allocated(), 0);
+    }
+
+    #[test]
+    fn test_bitmap_length_mismatch() {
+        let bitmap = from_bools(&[true; 10]);
+        let err = and(bitmap.slice(0, 5), bitmap.slice(0, 6)).unwrap_err();
+        assert_eq!(err.code(), StatusCode::Invalid);
+    }
+
+    #[test]
+    fn test_bit_slice() {
+        let bitmap = from_bools(&[true, false, true, true, false, true, true, true, true, false]);
+        let slice = bitmap.slice(3, 6);
+        assert_eq!(slice.len(), 6);
+        assert!(slice.get(0));
+        assert!(!slice.get(1));
+        assert_eq!(slice.count_set_bits(), 5);
+    }
+
+    #[test]
+    fn test_word_at() {
+        let bytes = [0b1010_1010u8, 0xff, 0, 0, 0, 0, 0, 0, 0b1];
+        assert_eq!(word_at(&bytes, 0), 0xffaa);
+        assert_eq!(word_at(&bytes, 1), 0x7fd5 | (1 << 63));
+        assert_eq!(word_at(&bytes, 8), 0xff | (1 << 56));
+    }
+
+}
@@ -0,0 +1,623 @@
+use std::fmt;
+#[cfg(unix)]
+use std::fs::File;
+use std::mem::{self, ManuallyDrop};
+#[cfg(unix)]
+use std::path::Path;
+use std::ptr;
+use std::slice;
+use std::sync::Arc;
+
+#[cfg(unix)]
+use libc;
+
+use errors::{ArrowError, StatusCode, Result};
+use mempool::{MemoryPoolRef, default_pool, ALIGNMENT};
+
+
+// arrow buffers are padded to a multiple of 64 bytes
+#[inline]
+pub fn padded_len(len: usize) -> usize {
+    (len + ALIGNMENT - 1) & !(ALIGNMENT - 1)
+}
+
+
+enum Deallocation {
+    Pool,
+    // base address and length of the mapping
+    #[cfg(unix)]
+    Unmap(*mut libc::c_void, usize)
+}
+
+
+// an owned, immutable memory region returned to its pool or unmapped on drop
+struct Bytes {
+    ptr: *const u8,
+    capacity: usize,
+    pool: MemoryPoolRef,
+    deallocation: Deallocation
+}
+
+
+unsafe impl Send for Bytes {}
+unsafe impl Sync for Bytes {}
+
+
+impl Drop for Bytes {
+
+    fn drop(&mut self) {
+        match self.deallocation {
+            Deallocation::Pool => unsafe {
+                self.pool.free(self.ptr as *mut u8, self.capacity);
+            },
+            #[cfg(unix)]
+            Deallocation::Unmap(base, len) => {
+                unsafe {
+                    libc::munmap(base, len);
+                }
+                self.pool.release_mapped(len);
+            }
+        }
+    }
+
+}
+
+
+// immutable, 64 byte aligned and padded memory shared between arrays,
+// cloning and slicing are zero-copy, except that mapped file regions are
+// unpadded and only aligned for aligned file offsets
+#[derive(Clone)]
+pub struct Buffer {
+    data: Arc<Bytes>,
+    offset: usize,
+    len: usize
+}
+
+
+impl Buffer {
+
+    pub fn from_slice<T: Copy>(values: &[T]) -> Result<Self> {
+        Self::from_slice_with_pool(values, default_pool())
+    }
+
+    pub fn from_slice_with_pool<T: Copy>(values: &[T], pool: MemoryPoolRef) -> Result<Self> {
+        let len = mem::size_of_val(values);
+        let capacity = padded_len(len);
+        let ptr = pool.allocate(capacity)?;
+        unsafe {
+            ptr::copy_nonoverlapping(values.as_ptr() as *const u8, ptr, len);
+        }
+        let bytes = Bytes {
+            ptr,
+            capacity,
+            pool,
+            deallocation: Deallocation::Pool
+        };
+        Ok(Buffer {
+            data: Arc::new(bytes),
+            offset: 0,
+            len
+        })
+    }
+
+    #[cfg(unix)]
+    pub fn from_file<P: AsRef<Path>>(path: P, offset: usize, len: usize) -> Result<Self> {
+        Self::from_file_with_pool(path, offset, len, default_pool())
+    }
+
+    // read-only buffer backed by a private mapping of len bytes of the file,
+    // the memory is only aligned if offset is a multiple of ALIGNMENT and never padded
+    #[cfg(unix)]
+    pub fn from_file_with_pool<P: AsRef<Path>>(path: P, offset: usize, len: usize,
+                                               pool: MemoryPoolRef) -> Result<Self> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len() as usize;
+        match offset.checked_add(len) {
+            Some(end) if end <= file_len => {},
+            _ => return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "cannot map {} bytes at offset {} of a file with length {}",
+                len, offset, file_len)))
+        }
+        if len == 0 {
+            return Self::from_slice_with_pool::<u8>(&[], pool);
+        }
+
+        // mmap requires the offset to be a multiple of the page size
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize };
+        let delta = offset % page_size;
+        let map_len = len + delta;
+        let base = unsafe {
+            use std::os::unix::io::AsRawFd;
+            libc::mmap(ptr::null_mut(), map_len, libc::PROT_READ, libc::MAP_PRIVATE,
+                       file.as_raw_fd(), (offset - delta) as libc::off_t)
+        };
+        if base == libc::MAP_FAILED {
+            return Err(::std::io::Error::last_os_error().into());
+        }
+        pool.record_mapped(map_len);
+
+        let bytes = Bytes {
+            ptr: unsafe { (base as *const u8).add(delta) },
+            capacity: len,
+            pool,
+            deallocation: Deallocation::Unmap(base, map_len)
+        };
+        Ok(Buffer {
+            data: Arc::new(bytes),
+            offset: 0,
+            len
+        })
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        match self.data.deallocation {
+            #[cfg(unix)]
+            Deallocation::Unmap(..) => true,
+            Deallocation::Pool => false
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // size of the underlying allocation including padding, just len for mapped buffers
+    pub fn capacity(&self) -> usize {
+        self.data.capacity
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn raw_data(&self) -> *const u8 {
+        unsafe { self.data.ptr.add(self.offset) }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.raw_data(), self.len) }
+    }
+
+    /// # Safety
+    ///
+    /// Every bit pattern must be a valid `T`.
+    pub unsafe fn typed_data<T>(&self) -> &[T] {
+        let size = mem::size_of::<T>();
+        assert_eq!(self.len % size, 0);
+        assert_eq!(self.raw_data() as usize % mem::align_of::<T>(), 0);
+        slice::from_raw_parts(self.raw_data() as *const T, self.len / size)
+    }
+
+    pub fn slice(&self, offset: usize, len: usize) -> Buffer {
+        assert!(offset + len <= self.len,
+                "slice [{}, {}) is out of bounds of buffer with length {}",
+                offset, offset + len, self.len);
+        Buffer {
+            data: self.data.clone(),
+            offset: self.offset + offset,
+            len
+        }
+    }
+
+    pub fn pool(&self) -> &MemoryPoolRef {
+        &self.data.pool
+    }
+
+}
+
+
+impl PartialEq for Buffer {
+
+    fn eq(&self, other: &Buffer) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+
+}
+
+
+impl fmt::Debug for Buffer {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Buffer {{ offset: {}, len: {}, data: {:?} }}",
+               self.offset, self.len, self.as_slice())
+    }
+
+}
+
+
+// growable, 64 byte aligned and padded buffer, freeze it to share the
+// contents as an immutable Buffer
+pub struct MutableBuffer {
+    ptr: *mut u8,
+    len: usize,
+    capacity: usize,
+    pool: MemoryPoolRef
+}
+
+
+unsafe impl Send for MutableBuffer {}
+unsafe impl Sync for MutableBuffer {}
+
+
+impl MutableBuffer {
+
+    pub fn new() -> Self {
+        Self::with_pool(default_pool())
+    }
+
+    pub fn with_pool(pool: MemoryPoolRef) -> Self {
+        MutableBuffer {
+            ptr: ALIGNMENT as *mut u8,
+            len: 0,
+            capacity: 0,
+            pool
+        }
+    }
+
+    pub fn with_capacity(capacity: usize, pool: MemoryPoolRef) -> Result<Self> {
+        let mut buffer = Self::with_pool(pool);
+        buffer.reserve(capacity)?;
+        Ok(buffer)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn pool(&self) -> &MemoryPoolRef {
+        &self.pool
+    }
+
+    pub fn raw_data(&self) -> *const u8 {
+        self.ptr
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+
+    /// # Safety
+    ///
+    /// Every bit pattern must be a valid `T`.
+    pub unsafe fn typed_data<T>(&self) -> &[T] {
+        let size = mem::size_of::<T>();
+        assert_eq!(self.len % size, 0);
+        slice::from_raw_parts(self.ptr as *const T, self.len / size)
+    }
+
+    /// # Safety
+    ///
+    /// Every bit pattern must be a valid `T`.
+    pub unsafe fn typed_data_mut<T>(&mut self) -> &mut [T] {
+        let size = mem::size_of::<T>();
+        assert_eq!(self.len % size, 0);
+        slice::from_raw_parts_mut(self.ptr as *mut T, self.len / size)
+    }
+
+    // makes room for at least additional more bytes, the capacity
+    // grows geometrically and the unused tail is kept zeroed
+    pub fn reserve(&mut self, additional: usize) -> Result<()> {
+        let required = match self.len.checked_add(additional) {
+            Some(required) => required,
+            None => return Err(ArrowError::new(StatusCode::OutOfMemory, format!(
+                "cannot reserve {} more bytes for a buffer of {}", additional, self.len)))
+        };
+        if required <= self.capacity {
+            return Ok(());
+        }
+        let capacity = ::std::cmp::max(padded_len(required), self.capacity * 2);
+        let ptr = unsafe { self.pool.reallocate(self.ptr, self.capacity, capacity)? };
+        unsafe {
+            ptr::write_bytes(ptr.add(self.capacity), 0, capacity - self.capacity);
+        }
+        self.ptr = ptr;
+        self.capacity = capacity;
+        Ok(())
+    }
+
+    // truncates or extends with zeros, truncated bytes are zeroed to keep the tail clean
+    pub fn resize(&mut self, len: usize) -> Result<()> {
+        if len > self.len {
+            self.reserve(len - self.len)?;
+        } else {
+            unsafe {
+                ptr::write_bytes(self.ptr.add(len), 0, self.len - len);
+            }
+        }
+        self.len = len;
+        Ok(())
+    }
+
+    pub fn extend_from_slice<T: Copy>(&mut self, values: &[T]) -> Result<()> {
+        let size = mem::size_of_val(values);
+        self.reserve(size)?;
+        unsafe {
+            ptr::copy_nonoverlapping(values.as_ptr() as *const u8, self.ptr.add(self.len), size);
+        }
+        self.len += size;
+        Ok(())
+    }
+
+    pub fn push<T: Copy>(&mut self, value: T) -> Result<()> {
+        let size = mem::size_of::<T>();
+        self.reserve(size)?;
+        unsafe {
+            ptr::write_unaligned(self.ptr.add(self.len) as *mut T, value);
+        }
+        self.len += size;
+        Ok(())
+    }
+
+    // hands the allocation over to an immutable Buffer without copying
+    pub fn freeze(self) -> Buffer {
+        // moves the pool out instead of cloning it, Drop must not run
+        let this = ManuallyDrop::new(self);
+        let bytes = Bytes {
+            ptr: this.ptr,
+            capacity: this.capacity,
+            pool: unsafe { ptr::read(&this.pool) },
+            deallocation: Deallocation::Pool
+        };
+        let len = this.len;
+        Buffer {
+            data: Arc::new(bytes),
+            offset: 0,
+            len
+        }
+    }
+
+}
+
+
+impl Default for MutableBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+impl Drop for MutableBuffer {
+
+    fn drop(&mut self) {
+        unsafe {
+            self.pool.free(self.ptr, self.capacity);
+        }
+    }
+
+}
+
+
+impl fmt::Debug for MutableBuffer {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MutableBuffer {{ len: {}, capacity: {}, data: {:?} }}",
+               self.len, self.capacity, self.as_slice())
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use mempool::{MemoryPool, DefaultMemoryPool, ChildMemoryPool, MemoryUsage};
+
+    #[test]
+    fn test_padded_len() {
+        assert_eq!(padded_len(0), 0);
+        assert_eq!(padded_len(1), 64);
+        assert_eq!(padded_len(64), 64);
+        assert_eq!(padded_len(65), 128);
+    }
+
+    #[test]
+    fn test_buffer_from_slice() {
+        let buf = Buffer::from_slice(&[1i32, 2, 3]).unwrap();
+        assert_eq!(buf.len(), 12);
+        assert_eq!(buf.capacity(), 64);
+        assert_eq!(buf.raw_data() as usize % ALIGNMENT, 0);
+        assert_eq!(unsafe { buf.typed_data::<i32>() }, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_buffer_padding_is_zeroed() {
+        let buf = Buffer::from_slice(&[0xffu8; 10]).unwrap();
+        let padded = unsafe { ::std::slice::from_raw_parts(buf.raw_data(), buf.capacity()) };
+        assert!(padded[10..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_buffer_slice() {
+        let buf = Buffer::from_slice(&[0u8, 1, 2, 3, 4, 5]).unwrap();
+        let sliced = buf.slice(2, 3);
+        assert_eq!(sliced.as_slice(), &[2, 3, 4]);
+        assert_eq!(sliced.raw_data(), unsafe { buf.raw_data().add(2) });
+
+        let sliced = sliced.slice(1, 2);
+        assert_eq!(sliced.offset(), 3);
+        assert_eq!(sliced.as_slice(), &[3, 4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_buffer_slice_out_of_bounds() {
+        let buf = Buffer::from_slice(&[0u8, 1, 2]).unwrap();
+        buf.slice(2, 2);
+    }
+
+    #[test]
+    fn test_buffer_shared_memory() {
+        let pool = Arc::new(DefaultMemoryPool::new());
+        let buf = Buffer::from_slice_with_pool(&[1u64; 10], pool.clone()).unwrap();
+        let cloned = buf.clone();
+        assert_eq!(pool.bytes_allocated(), 128);
+        assert_eq!(buf, cloned);
+
+        drop(buf);
+        assert_eq!(pool.bytes_allocated(), 128);
+        drop(cloned);
+        assert_eq!(pool.bytes_allocated(), 0);
+    }
+
+    #[cfg(unix)]
+    fn write_temp_file(name: &str, contents: &[u8]) -> ::std::path::PathBuf {
+        use std::io::Write;
+        let path = ::std::env::temp_dir().join(
+            format!("arrow-{}-{}", name, ::std::process::id()));
+        File::create(&path).unwrap().write_all(contents).unwrap();
+        path
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_buffer_from_file() {
+        let contents: Vec<u8> = (0..10000).map(|i| (i % 251) as u8).collect();
+        let path = write_temp_file("buffer-from-file", &contents);
+        let pool = Arc::new(DefaultMemoryPool::new());
+        {
+            let buf = Buffer::from_file_with_pool(&path, 0, 10000, pool.clone()).unwrap();
+            assert!(buf.is_mapped());
+            assert_eq!(buf.as_slice(), &contents[..]);
+            assert_eq!(buf.capacity(), 10000);
+            assert_eq!(pool.bytes_mapped(), 10000);
+            assert_eq!(pool.bytes_allocated(), 0);
+
+            // unaligned offset crossing a page boundary
+            let buf = Buffer::from_file_with_pool(&path, 4090, 100, pool.clone()).unwrap();
+            assert_eq!(buf.as_slice(), &contents[4090..4190]);
+            assert_eq!(buf.slice(10, 5).as_slice(), &contents[4100..4105]);
+        }
+        assert_eq!(pool.bytes_mapped(), 0);
+        ::std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_buffer_from_file_out_of_bounds() {
+        let path = write_temp_file("buffer-out-of-bounds", &[1, 2, 3]);
+        let err = Buffer::from_file(&path, 2, 2).unwrap_err();
+        assert_eq!(err.code(), StatusCode::Invalid);
+        let err = Buffer::from_file(&path, usize::MAX, 2).unwrap_err();
+        assert_eq!(err.code(), StatusCode::Invalid);
+        assert!(Buffer::from_file(&path, 3, 0).unwrap().is_empty());
+        ::std::fs::remove_file(&path).unwrap();
+
+        let err = Buffer::from_file(&path, 0, 1).unwrap_err();
+        assert_eq!(err.code(), StatusCode::IOError);
+    }
+
+    #[test]
+    fn test_mutable_buffer_push() {
+        let pool = Arc::new(DefaultMemoryPool::new());
+        let mut buf = MutableBuffer::with_pool(pool.clone());
+        assert_eq!(buf.capacity(), 0);
+        assert_eq!(pool.bytes_allocated(), 0);
+
+        buf.push(1i64).unwrap();
+        buf.push(2i64).unwrap();
+        assert_eq!(buf.len(), 16);
+        assert_eq!(buf.capacity(), 64);
+        assert_eq!(pool.bytes_allocated(), 64);
+        assert_eq!(unsafe { buf.typed_data::<i64>() }, &[1, 2]);
+    }
+
+    #[test]
+    fn test_mutable_buffer_reserve() {
+        let mut buf = MutableBuffer::with_capacity(10, Arc::new(DefaultMemoryPool::new())).unwrap();
+        assert_eq!(buf.capacity(), 64);
+        assert_eq!(buf.len(), 0);
+
+        buf.reserve(64).unwrap();
+        assert_eq!(buf.capacity(), 64);
+        buf.resize(65).unwrap();
+        assert_eq!(buf.capacity(), 128);
+        buf.reserve(1000).unwrap();
+        assert_eq!(buf.capacity(), 1088);
+        assert_eq!(buf.raw_data() as usize % ALIGNMENT, 0);
+    }
+
+    #[test]
+    fn test_mutable_buffer_extend_resize() {
+        let mut buf = MutableBuffer::new();
+        buf.extend_from_slice(&[1u16, 2, 3]).unwrap();
+        assert_eq!(buf.len(), 6);
+
+        buf.resize(10).unwrap();
+        assert_eq!(unsafe { buf.typed_data::<u16>() }, &[1, 2, 3, 0, 0]);
+
+        unsafe { buf.typed_data_mut::<u16>()[4] = 5; }
+        buf.resize(4).unwrap();
+        buf.resize(10).unwrap();
+        assert_eq!(unsafe { buf.typed_data::<u16>() }, &[1, 2, 0, 0, 0]);
+
+        buf.resize(2).unwrap();
+        let frozen = buf.freeze();
+        let padded = unsafe { slice::from_raw_parts(frozen.raw_data(), frozen.capacity()) };
+        assert_eq!(padded[..2], [1, 0]);
+        assert!(padded[2..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_mutable_buffer_reserve_overflow() {
+        let mut buf = MutableBuffer::new();
+        buf.push(1u8).unwrap();
+        assert_eq!(buf.reserve(usize::MAX).unwrap_err().code(), StatusCode::OutOfMemory);
+        assert_eq!(buf.len(), 1);
+    }
+
+    #[test]
+    fn test_mutable_buffer_freeze() {
+        let pool = Arc::new(DefaultMemoryPool::new());
+        let mut buf = MutableBuffer::with_pool(pool.clone());
+        buf.extend_from_slice(&[1u8, 2, 3, 4, 5]).unwrap();
+        let ptr = buf.raw_data();
+
+        let frozen = buf.freeze();
+        assert_eq!(frozen.raw_data(), ptr);
+        assert_eq!(frozen.as_slice(), &[1, 2, 3, 4, 5]);
+        assert_eq!(frozen.capacity(), 64);
+        assert_eq!(pool.bytes_allocated(), 64);
+
+        let sliced = frozen.slice(1, 2);
+        drop(frozen);
+        assert_eq!(sliced.as_slice(), &[2, 3]);
+        drop(sliced);
+        assert_eq!(pool.bytes_allocated(), 0);
+    }
+
+    #[test]
+    fn test_mutable_buffer_freeze_releases_pool() {
+        let root: MemoryPoolRef = Arc::new(DefaultMemoryPool::new());
+        let child: MemoryPoolRef = ChildMemoryPool::new(&root, "query");
+        let mut buf = MutableBuffer::with_pool(child.clone());
+        buf.push(1u64).unwrap();
+        assert_eq!(Arc::strong_count(&child), 2);
+
+        let frozen = buf.freeze();
+        assert_eq!(Arc::strong_count(&child), 2);
+        drop(frozen);
+        assert_eq!(Arc::strong_count(&child), 1);
+
+        drop(child);
+        assert!(MemoryUsage::of(&*root).children.is_empty());
+        assert_eq!(root.bytes_allocated(), 0);
+    }
+
+}
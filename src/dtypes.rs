@@ -1,37 +1,21 @@
-use std::mem;
+use std::convert::TryFrom;
 use std::fmt;
+use std::iter;
+use std::mem;
 
-use array::{PrimitiveData, ListData, BitMap};
-
+use array::{PrimitiveData, BooleanData, DecimalData, BinaryData, FixedSizeBinaryData, ListData,
+            StructData, UnionData, DictionaryData, MapData, FixedSizeListData};
+use errors::{ArrowError, StatusCode, Result};
+use half::f16;
+use mempool::MemoryPoolRef;
+use temporal::{Date, Time, DateTime};
+use types;
 
-//TODO: default implementations
+// the units are shared with the runtime types
+pub use types::{TimeUnit, DateUnit, IntervalUnit, UnionMode, Precision};
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub enum TimeUnit {
-    Second,
-    Milli,
-    Micro,
-    Nano
-}
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub enum DateUnit {
-    Day,
-    Milli
-}
-
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub enum IntervalUnit {
-    YearMonth,
-    DayTime
-}
-
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub enum Precision {
-    Half,
-    Single,
-    Double
-}
+//TODO: default implementations
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Boolean;
@@ -60,54 +44,158 @@ pub struct UInt32;
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct UInt64;
 
-// struct HalfFloat;
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct HalfFloat;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Float32;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Float64;
 
+// fixed point, values are stored unscaled in 128 bits
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-struct Decimal {
+pub struct Decimal {
     precision: i32,
     scale: i32
 }
 
-// type String = String;
-// type Binary = 
-// FixedSizedBinary(i32),  // byte_width
+// named Utf8 to avoid shadowing std's String
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Utf8;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Binary;
+
+// i64 offsets, for more than 2GB of value data
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct LargeUtf8;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-struct Time32(TimeUnit);
+pub struct LargeBinary;
 
+// byte_width
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-struct Time64(TimeUnit);
+pub struct FixedSizeBinary(i32);
 
+// seconds or milliseconds since midnight
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-struct Date32(DateUnit);
+pub struct Time32(TimeUnit);
+
+// microseconds or nanoseconds since midnight
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Time64(TimeUnit);
+
+// days or milliseconds since the epoch
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Date32(DateUnit);
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Date64(DateUnit);
+
+// since the epoch, the timezone is only carried along
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Timestamp {
+    unit: TimeUnit,
+    timezone: Option<String>
+}
 
+// number of months
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-struct Date64(DateUnit);
+pub struct IntervalYearMonth;
 
+// days in the upper and milliseconds in the lower half
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-struct Interval(IntervalUnit);
+pub struct IntervalDayTime;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct List<T: DataType>(pub T);
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct LargeList<T: DataType>(pub T);
+
+// list_size child values per slot
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct FixedSizeList<T: DataType>(T, i32);
+
+// children are type erased, so their fields carry the runtime types
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Struct {
+    fields: Vec<types::Field>
+}
+
+// integer keys into a shared array of values
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Dictionary<K: IndexType, V: DataType>(pub K, pub V);
+
+// a list of struct<key, item> entries per slot
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Map {
+    key: types::Field,
+    item: types::Field,
+    keys_sorted: bool
+}
+
+// each slot holds a value of the child registered under its type id
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Union {
+    mode: UnionMode,
+    type_ids: Vec<i8>,
+    fields: Vec<types::Field>
+}
+
 
 // every datatype mmust have an array type, nested types 
-pub trait DataType : Copy {
+pub trait DataType : Clone {
     type Data;
 
     fn name(&self) -> &str;
     fn bits(&self) -> usize;
-    fn empty(&self) -> Self::Data;            
+    fn empty(&self, pool: &MemoryPoolRef) -> Self::Data;
+    // the runtime counterpart used by fields and schemas
+    fn data_type(&self) -> types::DataType;            
 }
 
 
 pub trait PrimitiveType: DataType {
-    type Item;
+    type Item: NativeType;
+}
+
+
+mod private {
+    pub trait Sealed {}
+}
+
+
+// plain numbers valid for any bit pattern, sealed so arrays can read them from raw buffers
+pub trait NativeType: Copy + Default + private::Sealed {}
+
+
+macro_rules! native {
+    ($($T:ty),*) => (
+        $(
+            impl private::Sealed for $T {}
+            impl NativeType for $T {}
+        )*
+    )
+}
+
+
+native!(i8, i16, i32, i64, u8, u16, u32, u64, f16, f32, f64);
+
+
+// integers usable as offsets of variable length layouts
+pub trait OffsetSize: Copy + Ord + Send + Sync + 'static {
+    // wraps for negative offsets, so validate those first
+    fn to_usize(self) -> usize;
+    fn from_usize(value: usize) -> Option<Self>;
+}
+
+
+// integers usable as dictionary keys
+pub trait IndexType: PrimitiveType {
+    fn to_index(key: Self::Item) -> Option<usize>;
+    fn from_index(index: usize) -> Option<Self::Item>;
 }
 
 
@@ -121,18 +209,26 @@ pub trait ListType: DataType {
 }
 
 pub trait StructType: DataType {
+    fn fields(&self) -> &[types::Field];
 
+    fn field(&self, i: usize) -> Option<&types::Field> {
+        self.fields().get(i)
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.fields().iter().position(|f| f.name() == name)
+    }
 }
 
 
 // rename to numeric?
 macro_rules! primitive {
-    ($DT:ty, $T:ty, $name:expr) => (
+    ($DT:ty, $T:ty, $name:expr, $logical:ident) => (
         impl DataType for $DT {
             type Data = PrimitiveData<$DT>;
 
-            fn empty(&self) -> Self::Data {
-                Self::Data::new()
+            fn empty(&self, pool: &MemoryPoolRef) -> Self::Data {
+                Self::Data::new(pool)
             }
             
             fn name(&self) -> &str {
@@ -142,6 +238,10 @@ macro_rules! primitive {
             fn bits(&self) -> usize {
                 mem::size_of::<$T>() * 8
             }
+
+            fn data_type(&self) -> types::DataType {
+                types::DataType::$logical
+            }
         }
 
         impl PrimitiveType for $DT {
@@ -173,41 +273,795 @@ macro_rules! floating {
 }
 
 
-primitive!(Int8,   i8, "int8");
-primitive!(Int16, i16, "int16");
-primitive!(Int32, i32, "int32");
-primitive!(Int64, i64, "int64");
+primitive!(Int8,   i8, "int8", Int8);
+primitive!(Int16, i16, "int16", Int16);
+primitive!(Int32, i32, "int32", Int32);
+primitive!(Int64, i64, "int64", Int64);
 
-primitive!(UInt8,   u8, "uint8");
-primitive!(UInt16, u16, "uint16");
-primitive!(UInt32, u32, "uint32");
-primitive!(UInt64, u64, "uint64");
+primitive!(UInt8,   u8, "uint8", UInt8);
+primitive!(UInt16, u16, "uint16", UInt16);
+primitive!(UInt32, u32, "uint32", UInt32);
+primitive!(UInt64, u64, "uint64", UInt64);
 
-primitive!(Float32, f32, "float32"); // Float
-primitive!(Float64, f64, "float64"); // Double
+primitive!(HalfFloat, f16, "float16", HalfFloat);
+primitive!(Float32, f32, "float32", Float);
+primitive!(Float64, f64, "float64", Double);
 
+floating!(HalfFloat, Precision::Half);
 floating!(Float32, Precision::Single);
 floating!(Float64, Precision::Double);
 
 
-impl<T: DataType + Copy> DataType for List<T> {
-    type Data = ListData<T>;
+macro_rules! index {
+    ($DT:ty, $T:ty) => (
+        impl IndexType for $DT {
+
+            fn to_index(key: $T) -> Option<usize> {
+                usize::try_from(key).ok()
+            }
+
+            fn from_index(index: usize) -> Option<$T> {
+                <$T>::try_from(index).ok()
+            }
+
+        }
+    )
+}
+
+
+index!(Int8, i8);
+index!(Int16, i16);
+index!(Int32, i32);
+index!(Int64, i64);
+
+index!(UInt8, u8);
+index!(UInt16, u16);
+index!(UInt32, u32);
+index!(UInt64, u64);
+
+
+macro_rules! offset {
+    ($T:ty) => (
+        impl OffsetSize for $T {
+
+            fn to_usize(self) -> usize {
+                self as usize
+            }
+
+            fn from_usize(value: usize) -> Option<$T> {
+                <$T>::try_from(value).ok()
+            }
+
+        }
+    )
+}
+
+
+offset!(i32);
+offset!(i64);
+
+
+// bit packed, so not a primitive with a native item type
+impl DataType for Boolean {
+    type Data = BooleanData;
 
-    fn empty(&self) -> Self::Data {
-        Self::Data::new(self.0)
+    fn empty(&self, pool: &MemoryPoolRef) -> Self::Data {
+        Self::Data::new(pool)
     }
-            
+
+    fn name(&self) -> &str {
+        "bool"
+    }
+
+    fn bits(&self) -> usize {
+        1
+    }
+
+    fn data_type(&self) -> types::DataType {
+        types::DataType::Bool
+    }
+}
+
+
+// variable length, offsets into a contiguous value buffer
+macro_rules! binary {
+    ($DT:ty, $O:ty, $name:expr, $logical:ident) => (
+        impl DataType for $DT {
+            type Data = BinaryData<$O>;
+
+            fn empty(&self, pool: &MemoryPoolRef) -> Self::Data {
+                Self::Data::new(pool)
+            }
+
+            fn name(&self) -> &str {
+                $name
+            }
+
+            fn bits(&self) -> usize {
+                0
+            }
+
+            fn data_type(&self) -> types::DataType {
+                types::DataType::$logical
+            }
+        }
+    )
+}
+
+binary!(Utf8, i32, "utf8", String);
+binary!(Binary, i32, "binary", Binary);
+binary!(LargeUtf8, i64, "large_utf8", LargeString);
+binary!(LargeBinary, i64, "large_binary", LargeBinary);
+
+
+impl Decimal {
+
+    pub const MAX_PRECISION: i32 = 38;
+
+    pub fn new(precision: i32, scale: i32) -> Result<Decimal> {
+        if !(1..=Decimal::MAX_PRECISION).contains(&precision) {
+            return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "decimal precision must be between 1 and {}, got {}",
+                Decimal::MAX_PRECISION, precision)));
+        }
+        if !(0..=precision).contains(&scale) {
+            return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "decimal scale must be between 0 and precision {}, got {}", precision, scale)));
+        }
+        Ok(Decimal { precision, scale })
+    }
+
+    pub fn precision(&self) -> i32 {
+        self.precision
+    }
+
+    pub fn scale(&self) -> i32 {
+        self.scale
+    }
+
+    // checks that an unscaled value has at most precision digits
+    pub fn validate(&self, value: i128) -> Result<()> {
+        if value.unsigned_abs() >= 10u128.pow(self.precision as u32) {
+            return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "{} does not fit decimal({}, {})", self.format(value), self.precision,
+                self.scale)));
+        }
+        Ok(())
+    }
+
+    // parses strings like "-123.45" into an unscaled value, never rounds
+    pub fn parse(&self, text: &str) -> Result<i128> {
+        let invalid = || ArrowError::new(StatusCode::Invalid, format!(
+            "cannot parse {:?} as decimal({}, {})", text, self.precision, self.scale));
+
+        let (negative, digits) = match text.as_bytes().first() {
+            Some(b'-') => (true, &text[1..]),
+            Some(b'+') => (false, &text[1..]),
+            _ => (false, text)
+        };
+        let (integer, fraction) = match digits.find('.') {
+            Some(point) => (&digits[..point], &digits[point + 1..]),
+            None => (digits, "")
+        };
+        if integer.is_empty() && fraction.is_empty() || fraction.len() > self.scale as usize {
+            return Err(invalid());
+        }
+
+        let mut value: i128 = 0;
+        let padding = self.scale as usize - fraction.len();
+        let all = integer.bytes().chain(fraction.bytes()).chain(iter::repeat_n(b'0', padding));
+        for c in all {
+            if !c.is_ascii_digit() {
+                return Err(invalid());
+            }
+            value = value.checked_mul(10)
+                .and_then(|v| v.checked_add((c - b'0') as i128))
+                .ok_or_else(invalid)?;
+        }
+        let value = if negative { -value } else { value };
+        self.validate(value)?;
+        Ok(value)
+    }
+
+    pub fn format(&self, value: i128) -> String {
+        let digits = value.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        let sign = if value < 0 { "-" } else { "" };
+        if scale == 0 {
+            return format!("{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        format!("{}{}.{}", sign, integer, fraction)
+    }
+
+    // converts an unscaled value to another scale, fails instead of dropping digits
+    pub fn rescale(&self, value: i128, to: &Decimal) -> Result<i128> {
+        let rescaled = if to.scale >= self.scale {
+            10i128.checked_pow((to.scale - self.scale) as u32)
+                .and_then(|factor| value.checked_mul(factor))
+        } else {
+            let factor = 10i128.pow((self.scale - to.scale) as u32);
+            if value % factor == 0 { Some(value / factor) } else { None }
+        };
+        match rescaled {
+            Some(rescaled) => to.validate(rescaled).map(|_| rescaled),
+            None => Err(ArrowError::new(StatusCode::Invalid, format!(
+                "cannot rescale {} to decimal({}, {}) without losing digits", self.format(value),
+                to.precision, to.scale)))
+        }
+    }
+
+}
+
+
+impl DataType for Decimal {
+    type Data = DecimalData;
+
+    fn empty(&self, pool: &MemoryPoolRef) -> Self::Data {
+        Self::Data::new(pool)
+    }
+
     fn name(&self) -> &str {
-        "list"
+        "decimal"
+    }
+
+    fn bits(&self) -> usize {
+        mem::size_of::<i128>() * 8
+    }
+
+    fn data_type(&self) -> types::DataType {
+        types::DataType::Decimal { precision: self.precision, scale: self.scale }
+    }
+}
+
+
+fn invalid_unit<U: fmt::Debug>(dtype: &str, unit: U) -> ArrowError {
+    ArrowError::new(StatusCode::Invalid, format!("{:?} is not a valid unit for {}", unit, dtype))
+}
+
+
+fn units_per_second(unit: TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => 1,
+        TimeUnit::Milli => 1_000,
+        TimeUnit::Micro => 1_000_000,
+        TimeUnit::Nano => 1_000_000_000
+    }
+}
+
+
+// splits a value of the given unit into whole seconds and nanoseconds
+fn split_seconds(value: i64, unit: TimeUnit) -> (i64, u32) {
+    let per_second = units_per_second(unit);
+    let nanos = value.rem_euclid(per_second) * (1_000_000_000 / per_second);
+    (value.div_euclid(per_second), nanos as u32)
+}
+
+
+fn time_of_day(value: i64, unit: TimeUnit) -> Result<Time> {
+    value.checked_mul(1_000_000_000 / units_per_second(unit))
+        .and_then(Time::from_nanos_since_midnight)
+        .ok_or_else(|| ArrowError::new(StatusCode::Invalid, format!(
+            "{} {:?} is not a time of day", value, unit)))
+}
+
+
+fn date(value: i64, unit: DateUnit) -> Date {
+    match unit {
+        DateUnit::Day => Date::from_days_since_epoch(value),
+        DateUnit::Milli => Date::from_days_since_epoch(value.div_euclid(86_400_000))
+    }
+}
+
+
+impl Time32 {
+
+    pub fn new(unit: TimeUnit) -> Result<Time32> {
+        match unit {
+            TimeUnit::Second | TimeUnit::Milli => Ok(Time32(unit)),
+            _ => Err(invalid_unit("time32", unit))
+        }
+    }
+
+    pub fn unit(&self) -> TimeUnit {
+        self.0
+    }
+
+    pub fn to_time(&self, value: i32) -> Result<Time> {
+        time_of_day(value as i64, self.0)
+    }
+
+}
+
+
+impl Time64 {
+
+    pub fn new(unit: TimeUnit) -> Result<Time64> {
+        match unit {
+            TimeUnit::Micro | TimeUnit::Nano => Ok(Time64(unit)),
+            _ => Err(invalid_unit("time64", unit))
+        }
+    }
+
+    pub fn unit(&self) -> TimeUnit {
+        self.0
+    }
+
+    pub fn to_time(&self, value: i64) -> Result<Time> {
+        time_of_day(value, self.0)
+    }
+
+}
+
+
+impl Date32 {
+
+    pub fn new(unit: DateUnit) -> Result<Date32> {
+        match unit {
+            DateUnit::Day => Ok(Date32(unit)),
+            _ => Err(invalid_unit("date32", unit))
+        }
+    }
+
+    pub fn unit(&self) -> DateUnit {
+        self.0
+    }
+
+    pub fn to_date(&self, value: i32) -> Date {
+        date(value as i64, self.0)
+    }
+
+}
+
+
+impl Date64 {
+
+    pub fn new(unit: DateUnit) -> Result<Date64> {
+        match unit {
+            DateUnit::Milli => Ok(Date64(unit)),
+            _ => Err(invalid_unit("date64", unit))
+        }
+    }
+
+    pub fn unit(&self) -> DateUnit {
+        self.0
+    }
+
+    pub fn to_date(&self, value: i64) -> Date {
+        date(value, self.0)
+    }
+
+}
+
+
+impl Timestamp {
+
+    pub fn new(unit: TimeUnit, timezone: Option<&str>) -> Timestamp {
+        Timestamp { unit, timezone: timezone.map(String::from) }
+    }
+
+    pub fn unit(&self) -> TimeUnit {
+        self.unit
+    }
+
+    pub fn timezone(&self) -> Option<&str> {
+        self.timezone.as_deref()
+    }
+
+    // in UTC, converting to the timezone is up to the caller
+    pub fn to_datetime(&self, value: i64) -> DateTime {
+        let (seconds, nanos) = split_seconds(value, self.unit);
+        DateTime::from_timestamp(seconds, nanos)
+    }
+
+}
+
+
+// stored as plain integers, the unit lives on the type
+macro_rules! temporal {
+    ($DT:ident, $T:ty, $name:expr) => (
+        impl DataType for $DT {
+            type Data = PrimitiveData<$DT>;
+
+            fn empty(&self, pool: &MemoryPoolRef) -> Self::Data {
+                Self::Data::new(pool)
+            }
+
+            fn name(&self) -> &str {
+                $name
+            }
+
+            fn bits(&self) -> usize {
+                mem::size_of::<$T>() * 8
+            }
+
+            fn data_type(&self) -> types::DataType {
+                types::DataType::$DT(self.0)
+            }
+        }
+
+        impl PrimitiveType for $DT {
+            type Item = $T;
+        }
+    )
+}
+
+
+temporal!(Time32, i32, "time32");
+temporal!(Time64, i64, "time64");
+temporal!(Date32, i32, "date32");
+temporal!(Date64, i64, "date64");
+
+
+// the unit is fixed by the type, as the storage width depends on it
+macro_rules! interval {
+    ($DT:ident, $T:ty, $unit:ident) => (
+        impl DataType for $DT {
+            type Data = PrimitiveData<$DT>;
+
+            fn empty(&self, pool: &MemoryPoolRef) -> Self::Data {
+                Self::Data::new(pool)
+            }
+
+            fn name(&self) -> &str {
+                "interval"
+            }
+
+            fn bits(&self) -> usize {
+                mem::size_of::<$T>() * 8
+            }
+
+            fn data_type(&self) -> types::DataType {
+                types::DataType::Interval(IntervalUnit::$unit)
+            }
+        }
+
+        impl PrimitiveType for $DT {
+            type Item = $T;
+        }
+    )
+}
+
+
+interval!(IntervalYearMonth, i32, YearMonth);
+interval!(IntervalDayTime, i64, DayTime);
+
+
+impl DataType for Timestamp {
+    type Data = PrimitiveData<Timestamp>;
+
+    fn empty(&self, pool: &MemoryPoolRef) -> Self::Data {
+        Self::Data::new(pool)
+    }
+
+    fn name(&self) -> &str {
+        "timestamp"
+    }
+
+    fn bits(&self) -> usize {
+        64
+    }
+
+    fn data_type(&self) -> types::DataType {
+        types::DataType::Timestamp { unit: self.unit, timezone: self.timezone.clone() }
+    }
+}
+
+
+impl PrimitiveType for Timestamp {
+    type Item = i64;
+}
+
+
+impl FixedSizeBinary {
+
+    pub fn new(width: i32) -> Result<FixedSizeBinary> {
+        if width < 0 {
+            return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "fixed size binary width must not be negative, got {}", width)));
+        }
+        Ok(FixedSizeBinary(width))
+    }
+
+    pub fn width(&self) -> i32 {
+        self.0
+    }
+
+    pub fn byte_width(&self) -> usize {
+        self.0 as usize
+    }
+
+}
+
+
+impl DataType for FixedSizeBinary {
+    type Data = FixedSizeBinaryData;
+
+    fn empty(&self, pool: &MemoryPoolRef) -> Self::Data {
+        Self::Data::new(pool)
+    }
+
+    fn name(&self) -> &str {
+        "fixed_size_binary"
+    }
+
+    fn bits(&self) -> usize {
+        self.byte_width() * 8
+    }
+
+    fn data_type(&self) -> types::DataType {
+        types::DataType::FixedSizedBinary(self.0)
+    }
+}
+
+
+macro_rules! list {
+    ($DT:ident, $O:ty, $name:expr) => (
+        impl<T: DataType> DataType for $DT<T> {
+            type Data = ListData<T, $O>;
+
+            fn empty(&self, pool: &MemoryPoolRef) -> Self::Data {
+                Self::Data::new(self.0.clone(), pool)
+            }
+
+            fn name(&self) -> &str {
+                $name
+            }
+
+            fn bits(&self) -> usize {
+                0
+            }
+
+            fn data_type(&self) -> types::DataType {
+                types::DataType::$DT(Box::new(self.0.data_type()))
+            }
+
+        }
+
+        impl<T: DataType> ListType for $DT<T> {
+
+        }
+    )
+}
+
+
+list!(List, i32, "list");
+list!(LargeList, i64, "large_list");
+
+
+impl<T: DataType> FixedSizeList<T> {
+
+    pub fn new(value_type: T, size: i32) -> Result<FixedSizeList<T>> {
+        if size < 0 {
+            return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "fixed size list size must not be negative, got {}", size)));
+        }
+        Ok(FixedSizeList(value_type, size))
+    }
+
+    pub fn value_type(&self) -> &T {
+        &self.0
+    }
+
+    pub fn list_size(&self) -> usize {
+        self.1 as usize
+    }
+
+}
+
+
+impl<T: DataType> DataType for FixedSizeList<T> {
+    type Data = FixedSizeListData<T>;
+
+    fn empty(&self, pool: &MemoryPoolRef) -> Self::Data {
+        Self::Data::new(self.0.clone(), pool)
+    }
+
+    fn name(&self) -> &str {
+        "fixed_size_list"
     }
 
     fn bits(&self) -> usize {
         0
     }
 
+    fn data_type(&self) -> types::DataType {
+        types::DataType::FixedSizeList(Box::new(self.0.data_type()), self.1)
+    }
+
+}
+
+
+impl<T: DataType> ListType for FixedSizeList<T> {
+
+}
+
+
+impl Struct {
+
+    pub fn new(fields: Vec<types::Field>) -> Struct {
+        Struct { fields }
+    }
+
 }
 
 
-impl<T: DataType + Copy> ListType for List<T> {
+impl DataType for Struct {
+    type Data = StructData;
+
+    fn empty(&self, _pool: &MemoryPoolRef) -> Self::Data {
+        Self::Data::new()
+    }
+
+    fn name(&self) -> &str {
+        "struct"
+    }
+
+    fn bits(&self) -> usize {
+        0
+    }
+
+    fn data_type(&self) -> types::DataType {
+        types::DataType::Struct(self.fields.clone())
+    }
+
+}
+
+
+impl StructType for Struct {
+
+    fn fields(&self) -> &[types::Field] {
+        &self.fields
+    }
+
+}
+
+
+impl Union {
+
+    // type ids must be unique and non-negative, one per field
+    pub fn new(mode: UnionMode, type_ids: Vec<i8>, fields: Vec<types::Field>) -> Result<Union> {
+        if type_ids.len() != fields.len() {
+            return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "union has {} fields, but {} type ids", fields.len(), type_ids.len())));
+        }
+        for (i, &id) in type_ids.iter().enumerate() {
+            if id < 0 || type_ids[..i].contains(&id) {
+                return Err(ArrowError::new(StatusCode::Invalid, format!(
+                    "invalid or duplicate union type id {}", id)));
+            }
+        }
+        Ok(Union { mode, type_ids, fields })
+    }
+
+    pub fn mode(&self) -> UnionMode {
+        self.mode
+    }
+
+    pub fn type_ids(&self) -> &[i8] {
+        &self.type_ids
+    }
+
+    // index of the field registered under the type id
+    pub fn child_index(&self, type_id: i8) -> Option<usize> {
+        self.type_ids.iter().position(|&id| id == type_id)
+    }
+
+}
+
+
+impl DataType for Union {
+    type Data = UnionData;
+
+    fn empty(&self, pool: &MemoryPoolRef) -> Self::Data {
+        Self::Data::new(pool)
+    }
+
+    fn name(&self) -> &str {
+        "union"
+    }
+
+    fn bits(&self) -> usize {
+        0
+    }
+
+    fn data_type(&self) -> types::DataType {
+        types::DataType::Union {
+            mode: self.mode,
+            type_ids: self.type_ids.clone(),
+            fields: self.fields.clone()
+        }
+    }
+
+}
+
+
+impl StructType for Union {
+
+    fn fields(&self) -> &[types::Field] {
+        &self.fields
+    }
+
+}
+
+
+impl<K: IndexType, V: DataType> DataType for Dictionary<K, V> {
+    type Data = DictionaryData<K, V>;
+
+    fn empty(&self, pool: &MemoryPoolRef) -> Self::Data {
+        Self::Data::new(self.0.clone(), self.1.clone(), pool)
+    }
+
+    fn name(&self) -> &str {
+        "dictionary"
+    }
+
+    fn bits(&self) -> usize {
+        self.0.bits()
+    }
+
+    fn data_type(&self) -> types::DataType {
+        types::DataType::Dictionary {
+            index_type: Box::new(self.0.data_type()),
+            value_type: Box::new(self.1.data_type())
+        }
+    }
+
+}
+
+
+impl Map {
+
+    pub fn new(key: types::Field, item: types::Field, keys_sorted: bool) -> Result<Map> {
+        if key.nullable() {
+            return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "map key {} must not be nullable", key.name())));
+        }
+        Ok(Map { key, item, keys_sorted })
+    }
+
+    pub fn key(&self) -> &types::Field {
+        &self.key
+    }
+
+    pub fn item(&self) -> &types::Field {
+        &self.item
+    }
+
+    // only carried along, not verified
+    pub fn keys_sorted(&self) -> bool {
+        self.keys_sorted
+    }
+
+    pub fn entries(&self) -> Struct {
+        Struct::new(vec![self.key.clone(), self.item.clone()])
+    }
+
+}
+
+
+impl DataType for Map {
+    type Data = MapData;
+
+    fn empty(&self, pool: &MemoryPoolRef) -> Self::Data {
+        Self::Data::new(self.entries(), pool)
+    }
+
+    fn name(&self) -> &str {
+        "map"
+    }
+
+    fn bits(&self) -> usize {
+        0
+    }
+
+    fn data_type(&self) -> types::DataType {
+        types::DataType::Map {
+            key: Box::new(self.key.clone()),
+            item: Box::new(self.item.clone()),
+            keys_sorted: self.keys_sorted
+        }
+    }
 
 }
@@ -1,11 +1,14 @@
 use std::error;
+use std::fmt;
+use std::io;
+use std::result;
 
 
 // consider using error_chain or failure, probably the latter
 
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-enum StatusCode {
+pub enum StatusCode {
     OK = 0,
     OutOfMemory = 1,
     KeyError = 2,
@@ -23,18 +26,38 @@ enum StatusCode {
 // TODO newtype for using std errors
 
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-struct ArrowError {
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ArrowError {
     code: StatusCode,
     msg: String
 }
 
 
-impl error::Error for ArrowError {
+impl ArrowError {
+
+    pub fn new<S: Into<String>>(code: StatusCode, msg: S) -> ArrowError {
+        ArrowError {
+            code,
+            msg: msg.into()
+        }
+    }
+
+    pub fn code(&self) -> StatusCode {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.msg
+    }
+
+}
+
+
+impl fmt::Display for StatusCode {
 
-    fn description(&self) -> &str {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use self::StatusCode::*;
-        match self.code {
+        let text = match *self {
             OK => "OK",
             OutOfMemory => "Out of memory",
             KeyError => "Key error",
@@ -44,17 +67,30 @@ impl error::Error for ArrowError {
             UnknownError => "Unknown error",
             NotImplemented => "NotImplemented",
             _ => "Unknown"
-        }
+        };
+        write!(f, "{}", text)
     }
 
 }
+
+
+impl error::Error for ArrowError {}
 //TODO: implement conversion traits from std
 
 
+impl From<io::Error> for ArrowError {
+
+    fn from(err: io::Error) -> ArrowError {
+        ArrowError::new(StatusCode::IOError, err.to_string())
+    }
+
+}
+
+
 impl fmt::Display for ArrowError {
 
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.description())
+        write!(f, "{}: {}", self.code, self.msg)
     }
 
 }
@@ -62,3 +98,4 @@ impl fmt::Display for ArrowError {
 
 // alias, convention
 pub type Error = ArrowError;
+pub type Result<T> = result::Result<T, ArrowError>;
@@ -0,0 +1,278 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Sub, Mul, Div, Neg};
+
+
+// IEEE 754 binary16, arithmetic is done in f32 and rounded back
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Default)]
+pub struct f16(u16);
+
+
+const SIGN_MASK: u16 = 0x8000;
+const EXP_MASK: u16 = 0x7c00;
+const MAN_MASK: u16 = 0x03ff;
+
+
+// rounds the mantissa shifted right by shift bits to nearest, ties to even
+fn round_shift(mantissa: u64, shift: u32) -> u64 {
+    let truncated = mantissa >> shift;
+    let remainder = mantissa & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    if remainder > halfway || (remainder == halfway && truncated & 1 == 1) {
+        truncated + 1
+    } else {
+        truncated
+    }
+}
+
+
+impl f16 {
+
+    pub const ZERO: f16 = f16(0x0000);
+    pub const ONE: f16 = f16(0x3c00);
+    pub const MAX: f16 = f16(0x7bff);
+    pub const MIN: f16 = f16(0xfbff);
+    pub const MIN_POSITIVE: f16 = f16(0x0400);
+    pub const EPSILON: f16 = f16(0x1400);
+    pub const INFINITY: f16 = f16(0x7c00);
+    pub const NEG_INFINITY: f16 = f16(0xfc00);
+    pub const NAN: f16 = f16(0x7e00);
+
+    pub fn from_bits(bits: u16) -> f16 {
+        f16(bits)
+    }
+
+    pub fn to_bits(self) -> u16 {
+        self.0
+    }
+
+    // widening f32 to f64 is exact, so this rounds only once
+    pub fn from_f32(value: f32) -> f16 {
+        f16::from_f64(value as f64)
+    }
+
+    pub fn from_f64(value: f64) -> f16 {
+        let bits = value.to_bits();
+        let sign = ((bits >> 48) as u16) & SIGN_MASK;
+        let exp = ((bits >> 52) & 0x7ff) as i32;
+        let man = bits & ((1 << 52) - 1);
+
+        if exp == 0x7ff {
+            // keeps the top payload bits, quiet
+            return if man == 0 {
+                f16(sign | EXP_MASK)
+            } else {
+                f16(sign | EXP_MASK | 0x0200 | (man >> 42) as u16)
+            };
+        }
+        let e = exp - 1023;
+        if e > 15 {
+            return f16(sign | EXP_MASK);
+        }
+        if e >= -14 {
+            // a carry out of the mantissa correctly bumps the exponent, up to infinity
+            let half = (((e + 15) as u64) << 10) + round_shift(man, 42);
+            return f16(sign | half as u16);
+        }
+        if exp == 0 || e < -25 {
+            return f16(sign);
+        }
+        // subnormal, in units of 2^-24
+        let full = man | (1 << 52);
+        f16(sign | round_shift(full, (28 - e) as u32) as u16)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        let sign = ((self.0 & SIGN_MASK) as u32) << 16;
+        let exp = ((self.0 & EXP_MASK) >> 10) as u32;
+        let man = (self.0 & MAN_MASK) as u32;
+        match exp {
+            0x1f => f32::from_bits(sign | 0x7f80_0000 | (man << 13)),
+            0 => {
+                let value = man as f32 / (1 << 24) as f32;
+                if sign == 0 { value } else { -value }
+            },
+            _ => f32::from_bits(sign | ((exp + 127 - 15) << 23) | (man << 13))
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.to_f32() as f64
+    }
+
+    pub fn is_nan(self) -> bool {
+        self.0 & EXP_MASK == EXP_MASK && self.0 & MAN_MASK != 0
+    }
+
+    pub fn is_infinite(self) -> bool {
+        self.0 & !SIGN_MASK == EXP_MASK
+    }
+
+    pub fn is_finite(self) -> bool {
+        self.0 & EXP_MASK != EXP_MASK
+    }
+
+    pub fn is_sign_negative(self) -> bool {
+        self.0 & SIGN_MASK != 0
+    }
+
+}
+
+
+impl From<f16> for f32 {
+    fn from(value: f16) -> f32 {
+        value.to_f32()
+    }
+}
+
+
+impl From<f16> for f64 {
+    fn from(value: f16) -> f64 {
+        value.to_f64()
+    }
+}
+
+
+impl PartialEq for f16 {
+    fn eq(&self, other: &f16) -> bool {
+        self.to_f32() == other.to_f32()
+    }
+}
+
+
+impl PartialOrd for f16 {
+    fn partial_cmp(&self, other: &f16) -> Option<Ordering> {
+        self.to_f32().partial_cmp(&other.to_f32())
+    }
+}
+
+
+macro_rules! widening {
+    ($Op:ident, $op:ident) => (
+        impl $Op for f16 {
+            type Output = f16;
+
+            fn $op(self, other: f16) -> f16 {
+                f16::from_f32(self.to_f32().$op(other.to_f32()))
+            }
+        }
+    )
+}
+
+
+widening!(Add, add);
+widening!(Sub, sub);
+widening!(Mul, mul);
+widening!(Div, div);
+
+
+impl Neg for f16 {
+    type Output = f16;
+
+    fn neg(self) -> f16 {
+        f16(self.0 ^ SIGN_MASK)
+    }
+}
+
+
+impl fmt::Display for f16 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f32(), f)
+    }
+}
+
+
+impl fmt::Debug for f16 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_f32(), f)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(f16::ONE.to_f32(), 1.0);
+        assert_eq!(f16::MAX.to_f32(), 65504.0);
+        assert_eq!(f16::MIN.to_f32(), -65504.0);
+        assert_eq!(f16::MIN_POSITIVE.to_f32(), 2f32.powi(-14));
+        assert_eq!(f16::EPSILON.to_f32(), 2f32.powi(-10));
+        assert!(f16::NAN.is_nan());
+        assert!(f16::INFINITY.is_infinite());
+        assert!(f16::NEG_INFINITY.is_sign_negative());
+    }
+
+    #[test]
+    fn test_roundtrip_all_bits() {
+        for bits in 0..=u16::MAX {
+            let value = f16::from_bits(bits);
+            if value.is_nan() {
+                assert!(value.to_f32().is_nan());
+                assert!(f16::from_f32(value.to_f32()).is_nan());
+            } else {
+                assert_eq!(f16::from_f32(value.to_f32()).to_bits(), bits);
+                assert_eq!(f16::from_f64(value.to_f64()).to_bits(), bits);
+            }
+        }
+    }
+
+    #[test]
+    fn test_special_values() {
+        assert!(f16::from_f32(f32::NAN).is_nan());
+        assert!(f16::from_f64(-f64::NAN).is_nan());
+        assert_eq!(f16::from_f32(f32::INFINITY).to_bits(), 0x7c00);
+        assert_eq!(f16::from_f64(f64::NEG_INFINITY).to_bits(), 0xfc00);
+        assert_eq!(f16::from_f32(-0.0).to_bits(), 0x8000);
+        assert_eq!(f16::from_f32(1e6).to_bits(), 0x7c00);
+        assert_eq!(f16::from_f32(-1e6).to_bits(), 0xfc00);
+        // rounds up to infinity above MAX + half an ulp
+        assert_eq!(f16::from_f32(65520.0).to_bits(), 0x7c00);
+        assert_eq!(f16::from_f32(65519.0).to_bits(), 0x7bff);
+    }
+
+    #[test]
+    fn test_subnormals() {
+        let smallest = 2f64.powi(-24);
+        assert_eq!(f16::from_f64(smallest).to_bits(), 0x0001);
+        assert_eq!(f16::from_bits(0x0001).to_f64(), smallest);
+        assert_eq!(f16::from_bits(0x03ff).to_f64(), 1023.0 * smallest);
+        // ties to even, towards zero for the smallest one
+        assert_eq!(f16::from_f64(smallest / 2.0).to_bits(), 0x0000);
+        assert_eq!(f16::from_f64(smallest * 0.75).to_bits(), 0x0001);
+        assert_eq!(f16::from_f64(smallest * 1.5).to_bits(), 0x0002);
+        assert_eq!(f16::from_f64(smallest * 2.5).to_bits(), 0x0002);
+        assert_eq!(f16::from_f64(-smallest / 4.0).to_bits(), 0x8000);
+        assert_eq!(f16::from_f64(f64::MIN_POSITIVE).to_bits(), 0x0000);
+        // the largest subnormal rounds up into the normal range
+        assert_eq!(f16::from_f64(2f64.powi(-14) - smallest / 4.0).to_bits(), 0x0400);
+    }
+
+    #[test]
+    fn test_rounding() {
+        // 1 + 2^-11 is halfway between 1 and 1 + 2^-10, ties to even
+        assert_eq!(f16::from_f64(1.0 + 2f64.powi(-11)).to_bits(), 0x3c00);
+        assert_eq!(f16::from_f64(1.0 + 3.0 * 2f64.powi(-11)).to_bits(), 0x3c02);
+        assert_eq!(f16::from_f64(1.0 + 2f64.powi(-11) + 2f64.powi(-30)).to_bits(), 0x3c01);
+        assert_eq!(f16::from_f32(0.1).to_f32(), 0.099975586);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = f16::from_f32(1.5);
+        let b = f16::from_f32(0.25);
+        assert_eq!(a + b, f16::from_f32(1.75));
+        assert_eq!(a - b, f16::from_f32(1.25));
+        assert_eq!(a * b, f16::from_f32(0.375));
+        assert_eq!(a / b, f16::from_f32(6.0));
+        assert_eq!(-a, f16::from_f32(-1.5));
+        assert_eq!(f16::MAX + f16::MAX, f16::INFINITY);
+        assert!(f16::NAN != f16::NAN);
+        assert_eq!(f16::ZERO, -f16::ZERO);
+        assert!(a > b);
+        assert_eq!(a.to_string(), "1.5");
+    }
+}
@@ -1,7 +1,651 @@
-use std::heap::{Heap, Layout, Alloc};
+use std::alloc::{self, Layout};
+use std::collections::HashMap;
+use std::fmt;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, Weak};
+use std::thread;
 
+use errors::{ArrowError, StatusCode, Result};
 
-type Mempool = Heap;
+
+// arrow requires 8 bytes, but recommends 64 to be friendly with avx512
+pub const ALIGNMENT: usize = 1 << 6;
+
+
+pub trait MemoryPool: Send + Sync {
+
+    fn allocate(&self, size: usize) -> Result<*mut u8>;
+
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated by this pool with `old_size` bytes.
+    unsafe fn reallocate(&self, ptr: *mut u8, old_size: usize, new_size: usize) -> Result<*mut u8>;
+
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated by this pool with `size` bytes and must
+    /// not be used afterwards.
+    unsafe fn free(&self, ptr: *mut u8, size: usize);
+
+    // number of bytes currently allocated through this pool
+    fn bytes_allocated(&self) -> usize;
+
+    // peak of bytes_allocated
+    fn max_memory(&self) -> usize;
+
+    // file backed memory isn't allocated by the pool, but still recorded
+    fn record_mapped(&self, size: usize);
+
+    fn release_mapped(&self, size: usize);
+
+    fn bytes_mapped(&self) -> usize;
+
+    fn name(&self) -> &str {
+        "default"
+    }
+
+    // keeps track of a pool created with this one as its parent
+    fn add_child(&self, _child: Weak<dyn MemoryPool>) {}
+
+    fn children(&self) -> Vec<MemoryPoolRef> {
+        Vec::new()
+    }
+
+}
+
+
+pub type MemoryPoolRef = Arc<dyn MemoryPool>;
+
+
+// zero sized allocations don't touch the allocator
+#[inline]
+fn dangling() -> *mut u8 {
+    ALIGNMENT as *mut u8
+}
+
+
+fn layout(size: usize) -> Result<Layout> {
+    Layout::from_size_align(size, ALIGNMENT).map_err(|_| {
+        ArrowError::new(StatusCode::Invalid,
+                        format!("invalid allocation size {}", size))
+    })
+}
+
+
+fn out_of_memory(size: usize) -> ArrowError {
+    ArrowError::new(StatusCode::OutOfMemory,
+                    format!("malloc of size {} failed", size))
+}
+
+
+// byte accounting shared by the pool implementations
+struct Usage {
+    allocated: AtomicUsize,
+    max_memory: AtomicUsize,
+    mapped: AtomicUsize,
+    limit: Option<usize>
+}
+
+
+impl Usage {
+
+    fn new(limit: Option<usize>) -> Self {
+        Usage {
+            allocated: AtomicUsize::new(0),
+            max_memory: AtomicUsize::new(0),
+            mapped: AtomicUsize::new(0),
+            limit
+        }
+    }
+
+    // account bytes before touching the allocator, so concurrent
+    // allocations can't overshoot the limit
+    fn grow(&self, bytes: usize) -> Result<()> {
+        let mut current = self.allocated.load(Ordering::SeqCst);
+        loop {
+            let requested = current + bytes;
+            if let Some(limit) = self.limit {
+                if requested > limit {
+                    return Err(ArrowError::new(StatusCode::OutOfMemory, format!(
+                        "allocation of {} bytes exceeds the memory limit of {} bytes \
+                         ({} bytes in use)", bytes, limit, current)));
+                }
+            }
+            match self.allocated.compare_exchange(current, requested, Ordering::SeqCst,
+                                                  Ordering::SeqCst) {
+                Ok(_) => break,
+                Err(actual) => current = actual
+            }
+        }
+        self.max_memory.fetch_max(current + bytes, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn shrink(&self, bytes: usize) {
+        self.allocated.fetch_sub(bytes, Ordering::SeqCst);
+    }
+
+    fn bytes_allocated(&self) -> usize {
+        self.allocated.load(Ordering::SeqCst)
+    }
+
+    fn max_memory(&self) -> usize {
+        self.max_memory.load(Ordering::SeqCst)
+    }
+
+    fn record_mapped(&self, size: usize) {
+        self.mapped.fetch_add(size, Ordering::SeqCst);
+    }
+
+    fn release_mapped(&self, size: usize) {
+        self.mapped.fetch_sub(size, Ordering::SeqCst);
+    }
+
+    fn bytes_mapped(&self) -> usize {
+        self.mapped.load(Ordering::SeqCst)
+    }
+
+}
+
+
+// pools created with a pool as their parent, dropped ones are pruned on every add and get
+struct ChildPools(Mutex<Vec<Weak<dyn MemoryPool>>>);
+
+
+impl ChildPools {
+
+    fn new() -> Self {
+        ChildPools(Mutex::new(Vec::new()))
+    }
+
+    fn add(&self, child: Weak<dyn MemoryPool>) {
+        let mut children = self.0.lock().unwrap();
+        children.retain(|child| child.strong_count() > 0);
+        children.push(child);
+    }
+
+    // exposes the number of registered children, dead ones included
+    #[cfg(test)]
+    fn registered(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    fn get(&self) -> Vec<MemoryPoolRef> {
+        let mut children = self.0.lock().unwrap();
+        children.retain(|child| child.strong_count() > 0);
+        children.iter().filter_map(|child| child.upgrade()).collect()
+    }
+
+}
+
+
+pub struct DefaultMemoryPool {
+    usage: Usage,
+    children: ChildPools
+}
+
+
+impl DefaultMemoryPool {
+
+    pub fn new() -> Self {
+        DefaultMemoryPool {
+            usage: Usage::new(None),
+            children: ChildPools::new()
+        }
+    }
+
+    // allocations which would exceed limit bytes fail with OutOfMemory
+    pub fn with_limit(limit: usize) -> Self {
+        DefaultMemoryPool {
+            usage: Usage::new(Some(limit)),
+            children: ChildPools::new()
+        }
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.usage.limit
+    }
+
+}
+
+
+impl Default for DefaultMemoryPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+impl MemoryPool for DefaultMemoryPool {
+
+    fn allocate(&self, size: usize) -> Result<*mut u8> {
+        if size == 0 {
+            return Ok(dangling());
+        }
+        let layout = layout(size)?;
+        self.usage.grow(size)?;
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            self.usage.shrink(size);
+            return Err(out_of_memory(size));
+        }
+        Ok(ptr)
+    }
+
+    unsafe fn reallocate(&self, ptr: *mut u8, old_size: usize, new_size: usize) -> Result<*mut u8> {
+        if old_size == 0 {
+            return self.allocate(new_size);
+        }
+        if new_size == 0 {
+            self.free(ptr, old_size);
+            return Ok(dangling());
+        }
+        let old_layout = layout(old_size)?;
+        // realloc requires the new size to form a valid layout too
+        layout(new_size)?;
+        if new_size > old_size {
+            self.usage.grow(new_size - old_size)?;
+        }
+        let new_ptr = unsafe { alloc::realloc(ptr, old_layout, new_size) };
+        if new_ptr.is_null() {
+            if new_size > old_size {
+                self.usage.shrink(new_size - old_size);
+            }
+            return Err(out_of_memory(new_size));
+        }
+        if new_size < old_size {
+            self.usage.shrink(old_size - new_size);
+        }
+        Ok(new_ptr)
+    }
+
+    unsafe fn free(&self, ptr: *mut u8, size: usize) {
+        if size == 0 {
+            return;
+        }
+        unsafe {
+            alloc::dealloc(ptr, layout(size).unwrap());
+        }
+        self.usage.shrink(size);
+    }
+
+    fn bytes_allocated(&self) -> usize {
+        self.usage.bytes_allocated()
+    }
+
+    fn max_memory(&self) -> usize {
+        self.usage.max_memory()
+    }
+
+    fn record_mapped(&self, size: usize) {
+        self.usage.record_mapped(size);
+    }
+
+    fn release_mapped(&self, size: usize) {
+        self.usage.release_mapped(size);
+    }
+
+    fn bytes_mapped(&self) -> usize {
+        self.usage.bytes_mapped()
+    }
+
+    fn add_child(&self, child: Weak<dyn MemoryPool>) {
+        self.children.add(child);
+    }
+
+    fn children(&self) -> Vec<MemoryPoolRef> {
+        self.children.get()
+    }
+
+}
+
+
+// checked pool surrounds every allocation with guard bytes, keeping the
+// returned pointer aligned
+const GUARD_SIZE: usize = ALIGNMENT;
+const GUARD_BYTE: u8 = 0xab;
+const POISON_BYTE: u8 = 0xdd;
+
+
+struct Allocations {
+    live: HashMap<usize, usize>,
+    allocated: usize,
+    max_memory: usize
+}
+
+
+// debugging pool, tracks every live allocation, detects buffer overruns,
+// double frees and leaks, freed memory is poisoned before it is released
+pub struct CheckedMemoryPool {
+    inner: MemoryPoolRef,
+    allocations: Mutex<Allocations>,
+    children: ChildPools
+}
+
+
+impl CheckedMemoryPool {
+
+    pub fn new(inner: MemoryPoolRef) -> Self {
+        CheckedMemoryPool {
+            inner,
+            allocations: Mutex::new(Allocations {
+                live: HashMap::new(),
+                allocated: 0,
+                max_memory: 0
+            }),
+            children: ChildPools::new()
+        }
+    }
+
+    // a failed check panics while holding the lock, keep reporting afterwards
+    fn allocations(&self) -> MutexGuard<'_, Allocations> {
+        self.allocations.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // sizes of the allocations which haven't been freed yet
+    pub fn leaks(&self) -> Vec<usize> {
+        let allocations = self.allocations();
+        let mut sizes: Vec<usize> = allocations.live.values().cloned().collect();
+        sizes.sort();
+        sizes
+    }
+
+    // panics if the guard bytes of any live allocation were overwritten
+    pub fn verify(&self) {
+        let allocations = self.allocations();
+        for (ptr, size) in allocations.live.iter() {
+            check_guards(*ptr as *mut u8, *size);
+        }
+    }
+
+}
+
+
+fn check_guards(ptr: *mut u8, size: usize) {
+    let (front, back) = unsafe {
+        (::std::slice::from_raw_parts(ptr.sub(GUARD_SIZE), GUARD_SIZE),
+         ::std::slice::from_raw_parts(ptr.add(size), GUARD_SIZE))
+    };
+    if front.iter().any(|b| *b != GUARD_BYTE) {
+        panic!("buffer underrun detected before allocation {:p} of {} bytes", ptr, size);
+    }
+    if back.iter().any(|b| *b != GUARD_BYTE) {
+        panic!("buffer overrun detected after allocation {:p} of {} bytes", ptr, size);
+    }
+}
+
+
+impl MemoryPool for CheckedMemoryPool {
+
+    fn allocate(&self, size: usize) -> Result<*mut u8> {
+        if size == 0 {
+            return Ok(dangling());
+        }
+        let base = self.inner.allocate(size + 2 * GUARD_SIZE)?;
+        unsafe {
+            ptr::write_bytes(base, GUARD_BYTE, GUARD_SIZE);
+            ptr::write_bytes(base.add(GUARD_SIZE + size), GUARD_BYTE, GUARD_SIZE);
+        }
+        let ptr = unsafe { base.add(GUARD_SIZE) };
+
+        let mut allocations = self.allocations();
+        allocations.live.insert(ptr as usize, size);
+        allocations.allocated += size;
+        if allocations.allocated > allocations.max_memory {
+            allocations.max_memory = allocations.allocated;
+        }
+        Ok(ptr)
+    }
+
+    unsafe fn reallocate(&self, ptr: *mut u8, old_size: usize, new_size: usize) -> Result<*mut u8> {
+        let new_ptr = self.allocate(new_size)?;
+        unsafe {
+            ptr::copy_nonoverlapping(ptr, new_ptr, ::std::cmp::min(old_size, new_size));
+        }
+        self.free(ptr, old_size);
+        Ok(new_ptr)
+    }
+
+    unsafe fn free(&self, ptr: *mut u8, size: usize) {
+        if size == 0 {
+            return;
+        }
+        // don't panic while holding the lock
+        let allocated = {
+            let mut allocations = self.allocations();
+            let allocated = allocations.live.remove(&(ptr as usize));
+            if allocated == Some(size) {
+                allocations.allocated -= size;
+            }
+            allocated
+        };
+        match allocated {
+            Some(allocated) if allocated == size => {},
+            Some(allocated) => panic!("allocation {:p} of {} bytes freed with size {}",
+                                      ptr, allocated, size),
+            None => panic!("double free or free of unknown pointer {:p} of {} bytes",
+                           ptr, size)
+        }
+        check_guards(ptr, size);
+        unsafe {
+            let base = ptr.sub(GUARD_SIZE);
+            ptr::write_bytes(base, POISON_BYTE, size + 2 * GUARD_SIZE);
+            self.inner.free(base, size + 2 * GUARD_SIZE);
+        }
+    }
+
+    fn bytes_allocated(&self) -> usize {
+        self.allocations().allocated
+    }
+
+    fn max_memory(&self) -> usize {
+        self.allocations().max_memory
+    }
+
+    fn record_mapped(&self, size: usize) {
+        self.inner.record_mapped(size);
+    }
+
+    fn release_mapped(&self, size: usize) {
+        self.inner.release_mapped(size);
+    }
+
+    fn bytes_mapped(&self) -> usize {
+        self.inner.bytes_mapped()
+    }
+
+    fn name(&self) -> &str {
+        "checked"
+    }
+
+    fn add_child(&self, child: Weak<dyn MemoryPool>) {
+        self.children.add(child);
+    }
+
+    fn children(&self) -> Vec<MemoryPoolRef> {
+        self.children.get()
+    }
+
+}
+
+
+impl Drop for CheckedMemoryPool {
+
+    fn drop(&mut self) {
+        if thread::panicking() {
+            return;
+        }
+        let leaks = self.leaks();
+        if !leaks.is_empty() {
+            panic!("memory leak detected, {} allocations still alive with sizes {:?}",
+                   leaks.len(), leaks);
+        }
+    }
+
+}
+
+
+// accounts the allocations of an operator or query separately, while
+// allocating from and being subject to the limits of its parent
+pub struct ChildMemoryPool {
+    name: String,
+    parent: MemoryPoolRef,
+    usage: Usage,
+    children: ChildPools
+}
+
+
+impl ChildMemoryPool {
+
+    pub fn new<S: Into<String>>(parent: &MemoryPoolRef, name: S) -> Arc<Self> {
+        Self::create(parent, name.into(), None)
+    }
+
+    pub fn with_limit<S: Into<String>>(parent: &MemoryPoolRef, name: S,
+                                       limit: usize) -> Arc<Self> {
+        Self::create(parent, name.into(), Some(limit))
+    }
+
+    fn create(parent: &MemoryPoolRef, name: String, limit: Option<usize>) -> Arc<Self> {
+        let pool = Arc::new(ChildMemoryPool {
+            name,
+            parent: parent.clone(),
+            usage: Usage::new(limit),
+            children: ChildPools::new()
+        });
+        let child: Weak<ChildMemoryPool> = Arc::downgrade(&pool);
+        parent.add_child(child);
+        pool
+    }
+
+    pub fn parent(&self) -> &MemoryPoolRef {
+        &self.parent
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.usage.limit
+    }
+
+}
+
+
+impl MemoryPool for ChildMemoryPool {
+
+    fn allocate(&self, size: usize) -> Result<*mut u8> {
+        self.usage.grow(size)?;
+        self.parent.allocate(size).inspect_err(|_| self.usage.shrink(size))
+    }
+
+    unsafe fn reallocate(&self, ptr: *mut u8, old_size: usize, new_size: usize) -> Result<*mut u8> {
+        if new_size > old_size {
+            self.usage.grow(new_size - old_size)?;
+        }
+        let new_ptr = self.parent.reallocate(ptr, old_size, new_size).inspect_err(|_| {
+            if new_size > old_size {
+                self.usage.shrink(new_size - old_size);
+            }
+        })?;
+        if new_size < old_size {
+            self.usage.shrink(old_size - new_size);
+        }
+        Ok(new_ptr)
+    }
+
+    unsafe fn free(&self, ptr: *mut u8, size: usize) {
+        self.parent.free(ptr, size);
+        self.usage.shrink(size);
+    }
+
+    fn bytes_allocated(&self) -> usize {
+        self.usage.bytes_allocated()
+    }
+
+    fn max_memory(&self) -> usize {
+        self.usage.max_memory()
+    }
+
+    fn record_mapped(&self, size: usize) {
+        self.usage.record_mapped(size);
+        self.parent.record_mapped(size);
+    }
+
+    fn release_mapped(&self, size: usize) {
+        self.usage.release_mapped(size);
+        self.parent.release_mapped(size);
+    }
+
+    fn bytes_mapped(&self) -> usize {
+        self.usage.bytes_mapped()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn add_child(&self, child: Weak<dyn MemoryPool>) {
+        self.children.add(child);
+    }
+
+    fn children(&self) -> Vec<MemoryPoolRef> {
+        self.children.get()
+    }
+
+}
+
+
+// snapshot of a pool and its descendants
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MemoryUsage {
+    pub name: String,
+    pub bytes_allocated: usize,
+    pub max_memory: usize,
+    pub bytes_mapped: usize,
+    pub children: Vec<MemoryUsage>
+}
+
+
+impl MemoryUsage {
+
+    pub fn of(pool: &dyn MemoryPool) -> MemoryUsage {
+        MemoryUsage {
+            name: pool.name().to_string(),
+            bytes_allocated: pool.bytes_allocated(),
+            max_memory: pool.max_memory(),
+            bytes_mapped: pool.bytes_mapped(),
+            children: pool.children().iter().map(|c| MemoryUsage::of(c.as_ref())).collect()
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        writeln!(f, "{}{}: {} bytes allocated, {} bytes peak, {} bytes mapped",
+                 "  ".repeat(depth), self.name, self.bytes_allocated, self.max_memory,
+                 self.bytes_mapped)?;
+        for child in &self.children {
+            child.write(f, depth + 1)?;
+        }
+        Ok(())
+    }
+
+}
+
+
+impl fmt::Display for MemoryUsage {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, 0)
+    }
+
+}
+
+
+static DEFAULT_POOL: OnceLock<MemoryPoolRef> = OnceLock::new();
+
+
+// process wide pool used when no pool is passed explicitly
+pub fn default_pool() -> MemoryPoolRef {
+    DEFAULT_POOL.get_or_init(|| Arc::new(DefaultMemoryPool::new())).clone()
+}
 
 
 #[cfg(test)]
@@ -10,26 +654,217 @@ mod tests {
 
     #[test]
     fn test_mempool_allocate() {
-        let mut pool = Mempool::default();
-        let layout = Layout::from_size_align(100, 1 << 6).unwrap();
+        let pool = DefaultMemoryPool::new();
+        match pool.allocate(100) {
+            Ok(page) => {
+                assert_eq!(100, pool.bytes_allocated());
+                assert_eq!(100, pool.max_memory());
+                assert_eq!(0, page as usize % ALIGNMENT);
 
-        let excess = unsafe {
-            pool.alloc_excess(layout).unwrap()
-        };
+                unsafe { pool.free(page, 100) }
+                assert_eq!(0, pool.bytes_allocated());
+                assert_eq!(100, pool.max_memory());
+            },
+            Err(e) => panic!("{}", e.message())
+        }
+    }
+
+    #[test]
+    fn test_mempool_reallocate() {
+        let pool = DefaultMemoryPool::new();
+        let page = pool.allocate(10).unwrap();
+        unsafe { *page = 42; }
+
+        let page = unsafe { pool.reallocate(page, 10, 200) }.unwrap();
+        assert_eq!(unsafe { *page }, 42);
+        assert_eq!(200, pool.bytes_allocated());
+
+        let page = unsafe { pool.reallocate(page, 200, 50) }.unwrap();
+        assert_eq!(50, pool.bytes_allocated());
+        assert_eq!(200, pool.max_memory());
+
+        unsafe { pool.free(page, 50) }
+        assert_eq!(0, pool.bytes_allocated());
+    }
+
+    #[test]
+    fn test_mempool_limit() {
+        let pool = DefaultMemoryPool::with_limit(100);
+        assert_eq!(pool.limit(), Some(100));
+
+        let page = pool.allocate(60).unwrap();
+        match pool.allocate(60) {
+            Ok(_) => panic!("allocation should exceed the limit"),
+            Err(e) => assert_eq!(e.code(), StatusCode::OutOfMemory)
+        }
+        assert_eq!(60, pool.bytes_allocated());
+
+        let err = unsafe { pool.reallocate(page, 60, 120) }.unwrap_err();
+        assert_eq!(err.code(), StatusCode::OutOfMemory);
+        assert_eq!(60, pool.bytes_allocated());
+
+        let page = unsafe { pool.reallocate(page, 60, 100) }.unwrap();
+        assert_eq!(100, pool.bytes_allocated());
+        unsafe { pool.free(page, 100) }
+
+        let page = pool.allocate(100).unwrap();
+        unsafe { pool.free(page, 100) }
+        assert_eq!(0, pool.bytes_allocated());
+        assert_eq!(100, pool.max_memory());
+    }
+
+    #[test]
+    fn test_checked_mempool() {
+        let pool = CheckedMemoryPool::new(Arc::new(DefaultMemoryPool::new()));
+        let page = pool.allocate(10).unwrap();
+        assert_eq!(0, page as usize % ALIGNMENT);
+        unsafe { *page.add(9) = 1; }
+        assert_eq!(10, pool.bytes_allocated());
+        assert_eq!(pool.leaks(), vec![10]);
+        pool.verify();
 
-        println!("{:?}", excess);
-        // let mut pool = DefaultMemoryPool::new();
-        // match pool.allocate(100) {
-        //     Ok(page) => {
-        //         assert_eq!(100, pool.bytes_allocated());
-        //         assert_eq!(100, pool.max_memory());
-
-        //         pool.free(page, 100);
-        //         assert_eq!(0, pool.bytes_allocated());
-        //         assert_eq!(100, pool.max_memory());
-        //     },
-        //     Err(e) => panic!("{}", e.message())
-        // }
+        // the old allocation is alive until its contents are copied
+        let page = unsafe { pool.reallocate(page, 10, 100) }.unwrap();
+        assert_eq!(unsafe { *page.add(9) }, 1);
+        assert_eq!(100, pool.bytes_allocated());
+        assert_eq!(110, pool.max_memory());
+
+        unsafe { pool.free(page, 100) }
+        assert_eq!(0, pool.bytes_allocated());
+        assert!(pool.leaks().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "memory leak detected")]
+    fn test_checked_mempool_leak() {
+        let pool = CheckedMemoryPool::new(Arc::new(DefaultMemoryPool::new()));
+        pool.allocate(10).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "double free")]
+    fn test_checked_mempool_double_free() {
+        let pool = CheckedMemoryPool::new(Arc::new(DefaultMemoryPool::new()));
+        let page = pool.allocate(10).unwrap();
+        unsafe { pool.free(page, 10) }
+        unsafe { pool.free(page, 10) }
+    }
+
+    #[test]
+    #[should_panic(expected = "buffer overrun")]
+    fn test_checked_mempool_overrun() {
+        let pool = CheckedMemoryPool::new(Arc::new(DefaultMemoryPool::new()));
+        let page = pool.allocate(10).unwrap();
+        unsafe { *page.add(10) = 1; }
+        unsafe { pool.free(page, 10) }
+    }
+
+    #[test]
+    #[should_panic(expected = "buffer underrun")]
+    fn test_checked_mempool_underrun() {
+        let pool = CheckedMemoryPool::new(Arc::new(DefaultMemoryPool::new()));
+        let page = pool.allocate(10).unwrap();
+        unsafe { *page.sub(1) = 1; }
+        pool.verify();
+    }
+
+    #[test]
+    fn test_child_mempool() {
+        let root: MemoryPoolRef = Arc::new(DefaultMemoryPool::new());
+        let query: MemoryPoolRef = ChildMemoryPool::new(&root, "query");
+        let scan = ChildMemoryPool::new(&query, "scan");
+        let filter = ChildMemoryPool::new(&query, "filter");
+        assert_eq!(scan.name(), "scan");
+        assert_eq!(scan.parent().name(), "query");
+
+        let a = scan.allocate(100).unwrap();
+        let b = filter.allocate(30).unwrap();
+        assert_eq!(scan.bytes_allocated(), 100);
+        assert_eq!(filter.bytes_allocated(), 30);
+        assert_eq!(query.bytes_allocated(), 130);
+        assert_eq!(root.bytes_allocated(), 130);
+
+        let a = unsafe { scan.reallocate(a, 100, 40) }.unwrap();
+        assert_eq!(scan.bytes_allocated(), 40);
+        assert_eq!(scan.max_memory(), 100);
+        assert_eq!(root.bytes_allocated(), 70);
+
+        unsafe {
+            scan.free(a, 40);
+            filter.free(b, 30);
+        }
+        assert_eq!(query.bytes_allocated(), 0);
+        assert_eq!(root.bytes_allocated(), 0);
+        assert_eq!(query.max_memory(), 130);
+    }
+
+    #[test]
+    fn test_child_mempool_limits() {
+        let root: MemoryPoolRef = Arc::new(DefaultMemoryPool::with_limit(100));
+        let query: MemoryPoolRef = ChildMemoryPool::with_limit(&root, "query", 50);
+        let other = ChildMemoryPool::new(&root, "other");
+
+        // the child's own limit
+        let err = query.allocate(60).unwrap_err();
+        assert_eq!(err.code(), StatusCode::OutOfMemory);
+        assert_eq!(query.bytes_allocated(), 0);
+
+        // the parent's limit, shared with the siblings
+        let a = other.allocate(80).unwrap();
+        let err = query.allocate(30).unwrap_err();
+        assert_eq!(err.code(), StatusCode::OutOfMemory);
+        assert_eq!(query.bytes_allocated(), 0);
+        assert_eq!(root.bytes_allocated(), 80);
+
+        unsafe { other.free(a, 80) }
+        let b = query.allocate(30).unwrap();
+        unsafe { query.free(b, 30) }
+    }
+
+    #[test]
+    fn test_child_pools_pruned_on_add() {
+        let root = Arc::new(DefaultMemoryPool::new());
+        let parent: MemoryPoolRef = root.clone();
+        for _ in 0..100 {
+            let _query = ChildMemoryPool::new(&parent, "query");
+        }
+        assert_eq!(root.children.registered(), 1);
+        let _live = ChildMemoryPool::new(&parent, "live");
+        let _other = ChildMemoryPool::new(&parent, "other");
+        assert_eq!(root.children.registered(), 2);
+    }
+
+    #[test]
+    fn test_memory_usage_tree() {
+        let root: MemoryPoolRef = Arc::new(DefaultMemoryPool::new());
+        let query: MemoryPoolRef = ChildMemoryPool::new(&root, "query");
+        let scan = ChildMemoryPool::new(&query, "scan");
+        let page = scan.allocate(64).unwrap();
+        {
+            let _dropped = ChildMemoryPool::new(&root, "dropped");
+        }
+
+        let usage = MemoryUsage::of(root.as_ref());
+        assert_eq!(usage.name, "default");
+        assert_eq!(usage.bytes_allocated, 64);
+        assert_eq!(usage.children.len(), 1);
+        assert_eq!(usage.children[0].name, "query");
+        assert_eq!(usage.children[0].children[0].name, "scan");
+        assert_eq!(usage.children[0].children[0].bytes_allocated, 64);
+        assert_eq!(usage.to_string(), "default: 64 bytes allocated, 64 bytes peak, 0 bytes mapped
+  query: 64 bytes allocated, 64 bytes peak, 0 bytes mapped
+    scan: 64 bytes allocated, 64 bytes peak, 0 bytes mapped
+");
+        unsafe { scan.free(page, 64) }
+    }
+
+    #[test]
+    fn test_mempool_zero_size() {
+        let pool = DefaultMemoryPool::new();
+        let page = pool.allocate(0).unwrap();
+        assert!(!page.is_null());
+        assert_eq!(0, pool.bytes_allocated());
+        unsafe { pool.free(page, 0) }
     }
 
 }
@@ -0,0 +1,258 @@
+use std::fmt;
+
+
+// civil calendar values, proleptic gregorian and always UTC
+
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct Date {
+    year: i64,
+    month: u32,
+    day: u32
+}
+
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct Time {
+    hour: u32,
+    minute: u32,
+    second: u32,
+    nanosecond: u32
+}
+
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct DateTime {
+    date: Date,
+    time: Time
+}
+
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+
+
+impl Date {
+
+    pub fn new(year: i64, month: u32, day: u32) -> Option<Date> {
+        if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+            return None;
+        }
+        Some(Date { year, month, day })
+    }
+
+    // see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    pub fn from_days_since_epoch(days: i64) -> Date {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+        Date { year, month, day }
+    }
+
+    pub fn days_since_epoch(&self) -> i64 {
+        let year = if self.month <= 2 { self.year - 1 } else { self.year };
+        let era = year.div_euclid(400);
+        let yoe = year - era * 400;
+        let mp = if self.month > 2 { self.month - 3 } else { self.month + 9 } as i64;
+        let doy = (153 * mp + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    pub fn year(&self) -> i64 {
+        self.year
+    }
+
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+}
+
+
+impl Time {
+
+    pub fn new(hour: u32, minute: u32, second: u32, nanosecond: u32) -> Option<Time> {
+        if hour > 23 || minute > 59 || second > 59 || nanosecond as i64 >= NANOS_PER_SECOND {
+            return None;
+        }
+        Some(Time { hour, minute, second, nanosecond })
+    }
+
+    // None unless 0 <= nanos < 24h
+    pub fn from_nanos_since_midnight(nanos: i64) -> Option<Time> {
+        if !(0..SECONDS_PER_DAY * NANOS_PER_SECOND).contains(&nanos) {
+            return None;
+        }
+        let seconds = nanos / NANOS_PER_SECOND;
+        Some(Time {
+            hour: (seconds / 3600) as u32,
+            minute: (seconds / 60 % 60) as u32,
+            second: (seconds % 60) as u32,
+            nanosecond: (nanos % NANOS_PER_SECOND) as u32
+        })
+    }
+
+    pub fn nanos_since_midnight(&self) -> i64 {
+        let seconds = self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64;
+        seconds * NANOS_PER_SECOND + self.nanosecond as i64
+    }
+
+    pub fn hour(&self) -> u32 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u32 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u32 {
+        self.second
+    }
+
+    pub fn nanosecond(&self) -> u32 {
+        self.nanosecond
+    }
+
+}
+
+
+impl DateTime {
+
+    pub fn new(date: Date, time: Time) -> DateTime {
+        DateTime { date, time }
+    }
+
+    pub fn from_timestamp(seconds: i64, nanosecond: u32) -> DateTime {
+        let days = seconds.div_euclid(SECONDS_PER_DAY);
+        let nanos = seconds.rem_euclid(SECONDS_PER_DAY) * NANOS_PER_SECOND + nanosecond as i64;
+        DateTime {
+            date: Date::from_days_since_epoch(days),
+            time: Time::from_nanos_since_midnight(nanos).expect("nanosecond out of range")
+        }
+    }
+
+    pub fn date(&self) -> Date {
+        self.date
+    }
+
+    pub fn time(&self) -> Time {
+        self.time
+    }
+
+}
+
+
+impl fmt::Display for Date {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.year < 0 {
+            write!(f, "-{:04}-{:02}-{:02}", -self.year, self.month, self.day)
+        } else {
+            write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+        }
+    }
+
+}
+
+
+impl fmt::Display for Time {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)?;
+        if self.nanosecond > 0 {
+            let fraction = format!("{:09}", self.nanosecond);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        Ok(())
+    }
+
+}
+
+
+impl fmt::Display for DateTime {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}T{}", self.date, self.time)
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_from_days() {
+        assert_eq!(Date::from_days_since_epoch(0), Date::new(1970, 1, 1).unwrap());
+        assert_eq!(Date::from_days_since_epoch(-1), Date::new(1969, 12, 31).unwrap());
+        assert_eq!(Date::from_days_since_epoch(11_016), Date::new(2000, 2, 29).unwrap());
+        assert_eq!(Date::from_days_since_epoch(-719_468), Date::new(0, 3, 1).unwrap());
+        assert_eq!(Date::new(2023, 7, 4).unwrap().to_string(), "2023-07-04");
+        assert_eq!(Date::from_days_since_epoch(-719_529).to_string(), "-0001-12-31");
+    }
+
+    #[test]
+    fn test_date_roundtrip() {
+        for days in (-1_000_000..1_000_000).step_by(997) {
+            assert_eq!(Date::from_days_since_epoch(days).days_since_epoch(), days);
+        }
+    }
+
+    #[test]
+    fn test_date_validation() {
+        assert!(Date::new(2000, 2, 29).is_some());
+        assert!(Date::new(1900, 2, 29).is_none());
+        assert!(Date::new(2001, 4, 31).is_none());
+        assert!(Date::new(2001, 13, 1).is_none());
+        assert!(Date::new(2001, 1, 0).is_none());
+    }
+
+    #[test]
+    fn test_time() {
+        let time = Time::from_nanos_since_midnight(45_296_500_000_000).unwrap();
+        assert_eq!((time.hour(), time.minute(), time.second()), (12, 34, 56));
+        assert_eq!(time.nanosecond(), 500_000_000);
+        assert_eq!(time.to_string(), "12:34:56.5");
+        assert_eq!(time.nanos_since_midnight(), 45_296_500_000_000);
+        assert_eq!(Time::new(0, 0, 0, 0).unwrap().to_string(), "00:00:00");
+
+        assert!(Time::from_nanos_since_midnight(-1).is_none());
+        assert!(Time::from_nanos_since_midnight(86_400 * 1_000_000_000).is_none());
+        assert!(Time::new(24, 0, 0, 0).is_none());
+    }
+
+    #[test]
+    fn test_datetime() {
+        let dt = DateTime::from_timestamp(1_500_000_000, 123_000);
+        assert_eq!(dt.to_string(), "2017-07-14T02:40:00.000123");
+        let dt = DateTime::from_timestamp(-1, 0);
+        assert_eq!(dt.to_string(), "1969-12-31T23:59:59");
+    }
+}
@@ -1,4 +1,7 @@
 use std::fmt;
+
+use array::ArrayRef;
+use errors::{ArrowError, StatusCode, Result};
 use util::KeyValueMetadata;
 
 
@@ -22,6 +25,12 @@ pub enum IntervalUnit {
     DayTime
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum UnionMode {
+    Sparse,
+    Dense
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum Precision {
     Half,
@@ -52,16 +61,18 @@ pub enum DataType {
 
     String,
     Binary,
+    LargeString,
+    LargeBinary,
     FixedSizedBinary(i32),  // byte_width
     // might prefer tuple variants instead of struct ones
     Time32(TimeUnit),
     Time64(TimeUnit),
-    Date32(TimeUnit),
-    Date64(TimeUnit),
+    Date32(DateUnit),
+    Date64(DateUnit),
     Timestamp {
         unit: TimeUnit,
         // makes uncopyable this variant, might try to use a non-string timezeone, like variants from chro
-        timezone: String
+        timezone: Option<String>
     },
     Interval(IntervalUnit),
     Decimal {
@@ -69,10 +80,25 @@ pub enum DataType {
         scale: i32
     },
     List(Box<DataType>),
-    Struct,
-    Union,
-    Dictionary,
-    Map
+    LargeList(Box<DataType>),
+    FixedSizeList(Box<DataType>, i32),  // list_size
+    Struct(Vec<Field>),
+    Union {
+        mode: UnionMode,
+        // one per field
+        type_ids: Vec<i8>,
+        fields: Vec<Field>
+    },
+    Dictionary {
+        index_type: Box<DataType>,
+        value_type: Box<DataType>
+    },
+    Map {
+        // never nullable
+        key: Box<Field>,
+        item: Box<Field>,
+        keys_sorted: bool
+    }
 }
 
 // Kind enum
@@ -80,7 +106,7 @@ pub enum DataType {
 
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
-struct Field {
+pub struct Field {
     name: String,
     dtype: DataType, // set lifetime
     nullable: bool,
@@ -90,7 +116,7 @@ struct Field {
 
 //TODO: use indexmap instead
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
-struct Schema {
+pub struct Schema {
     fields: Vec<Field>,
     //name_to_index: HashMap<String, usize>,
     metadata: Option<KeyValueMetadata>
@@ -101,6 +127,7 @@ impl DataType {
     pub fn bit_width(&self) -> i32 {
         use self::DataType::*;
         match *self {
+            NA => 0,
             Bool => 1,
 
             Int8 | UInt8 => 8,
@@ -108,24 +135,30 @@ impl DataType {
             Int32 | UInt32 | Float => 32,
             Int64 | UInt64 | Double => 64,
 
-            Time32(_) | Date32(_) => 32,
-            Time64(_) | Date64(_) | Interval(_) => 64,
+            Time32(_) | Date32(_) | Interval(IntervalUnit::YearMonth) => 32,
+            Time64(_) | Date64(_) | Interval(IntervalUnit::DayTime) => 64,
 
             FixedSizedBinary(bytes) => bytes * 8,
 
-            //Timestamp { .. } => 64,
-            //Interval { .. } => 64,
+            Timestamp { .. } => 64,
 
-            //Decimal { .. } => 16 * 8,
+            Decimal { .. } => 16 * 8,
 
-            //Dictionary { ref index_type, .. } => index_type.bit_width(),
+            Dictionary { ref index_type, .. } => index_type.bit_width(),
 
             _ => panic!("{:?} is not fixed width type", self)
         }
     }
 
     pub fn name(&self) -> String {
-        format!("{:?}", self).to_lowercase()
+        match *self {
+            // the debug output would include the children
+            DataType::Struct(_) => "struct".to_string(),
+            DataType::Union { .. } => "union".to_string(),
+            DataType::Dictionary { .. } => "dictionary".to_string(),
+            DataType::Map { .. } => "map".to_string(),
+            _ => format!("{:?}", self).to_lowercase()
+        }
     }
 
     // try_from(str)
@@ -137,6 +170,29 @@ impl fmt::Display for DataType {
         use self::DataType::*;
         let text = match *self {
             List(ref dtype) => format!("list<item: {}>", dtype.name()),
+            LargeList(ref dtype) => format!("large_list<item: {}>", dtype.name()),
+            FixedSizeList(ref dtype, size) => {
+                format!("fixed_size_list<item: {}>[{}]", dtype.name(), size)
+            },
+            Struct(ref fields) => {
+                let fields: Vec<_> = fields.iter().map(|f| f.to_string()).collect();
+                format!("struct<{}>", fields.join(", "))
+            },
+            Dictionary { ref index_type, ref value_type } => {
+                format!("dictionary<values={}, indices={}>", value_type, index_type)
+            },
+            Map { ref key, ref item, keys_sorted } => {
+                let sorted = if keys_sorted { ", sorted" } else { "" };
+                format!("map<{}, {}{}>", key, item, sorted)
+            },
+            Union { mode, ref type_ids, ref fields } => {
+                let fields: Vec<_> = type_ids.iter().zip(fields)
+                    .map(|(id, f)| format!("{}={}", f, id))
+                    .collect();
+                let mode = format!("{:?}", mode).to_lowercase();
+                format!("union[{}]<{}>", mode, fields.join(", "))
+            },
+            Decimal { precision, scale } => format!("decimal({}, {})", precision, scale),
             _ => self.name()
         };
         write!(f, "{}", text)
@@ -150,8 +206,8 @@ impl Field {
     pub fn new(name: &str, dtype: DataType, nullable: bool) -> Field {
         Field {
             name: String::from(name),
-            dtype: dtype,
-            nullable: nullable,
+            dtype,
+            nullable,
             metadata: None
         }
     }
@@ -162,8 +218,8 @@ impl Field {
                          metadata: KeyValueMetadata) -> Field {
         Field {
             name: String::from(name),
-            dtype: dtype,
-            nullable: nullable,
+            dtype,
+            nullable,
             metadata: Some(metadata)
         }
     }
@@ -223,6 +279,27 @@ impl Schema {
         // TODO: arrow builds a name_to_index mapping on first use
         self.fields.iter().find(|f| f.name() == name)
     }
+
+    // checks that the columns match the fields' types and nullability
+    pub fn validate(&self, columns: &[ArrayRef]) -> Result<()> {
+        if columns.len() != self.fields.len() {
+            return Err(ArrowError::new(StatusCode::Invalid, format!(
+                "schema has {} fields, got {} columns", self.fields.len(), columns.len())));
+        }
+        for (field, column) in self.fields.iter().zip(columns) {
+            if column.data_type() != field.dtype {
+                return Err(ArrowError::new(StatusCode::TypeError, format!(
+                    "column {} should be {}, got {}", field.name, field.dtype,
+                    column.data_type())));
+            }
+            if !field.nullable && column.null_count() > 0 {
+                return Err(ArrowError::new(StatusCode::Invalid, format!(
+                    "column {} is not nullable, but contains {} nulls", field.name,
+                    column.null_count())));
+            }
+        }
+        Ok(())
+    }
 }
 
 
@@ -250,8 +327,8 @@ mod tests {
 
         assert_eq!(f0.name(), "f0");
         assert_eq!(f0.dtype(), &Int32);
-        assert_eq!(f0.nullable(), true);
-        assert_eq!(f0_nn.nullable(), false)
+        assert!(f0.nullable());
+        assert!(!f0_nn.nullable())
     }
 
     #[test]
@@ -312,6 +389,32 @@ mod tests {
         assert_eq!(schema.to_string(), expected);
     }
 
+    #[test]
+    fn test_schema_validate() {
+        use std::sync::Arc;
+        use array::{Int32Array, UInt8Array};
+        use dtypes;
+
+        let schema = Schema::new(vec![Field::new("f0", Int32, true),
+                                      Field::new("f1", UInt8, false)]);
+        let mut f0 = Int32Array::new(dtypes::Int32);
+        f0.push_null().unwrap();
+        let mut f1 = UInt8Array::new(dtypes::UInt8);
+        f1.push(1).unwrap();
+        let f0: ArrayRef = Arc::new(f0);
+        let f1: ArrayRef = Arc::new(f1);
+
+        assert!(schema.validate(&[f0.clone(), f1.clone()]).is_ok());
+        assert_eq!(schema.validate(&[]).unwrap_err().code(), StatusCode::Invalid);
+        assert_eq!(schema.validate(&[f1.clone(), f0.clone()]).unwrap_err().code(),
+                   StatusCode::TypeError);
+
+        let mut nulls = UInt8Array::new(dtypes::UInt8);
+        nulls.push_null().unwrap();
+        let err = schema.validate(&[f0, Arc::new(nulls)]).unwrap_err();
+        assert_eq!(err.code(), StatusCode::Invalid);
+    }
+
     #[test]
     fn test_schema_get_field_by_name() {
         let f0 = Field::new("f0", Int32, true);
@@ -32,7 +32,7 @@ mod tests {
         let keys = vec!["foo", "bar"];
         let values = vec!["bizz", "buzz"];
 
-        let meta = KeyValueMetadata::new(keys, values);
+        let _meta = KeyValueMetadata::new(keys, values);
     }
 
 }